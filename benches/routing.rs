@@ -0,0 +1,73 @@
+//! Routing benchmarks
+//!
+//! `dispatch_no_middleware` isolates matching and handler invocation from the
+//! rest of the request pipeline; `dispatch` measures the full path.
+
+use aws_lambda_router::middleware::LoggingMiddleware;
+use aws_lambda_router::{handler, Context, Request, Response, Result, Router};
+use criterion::{criterion_group, criterion_main, Criterion};
+use serde_json::{json, Value};
+
+async fn ok(_req: Request, _ctx: Context) -> Result<Response> {
+    Ok(Response::ok(json!({})))
+}
+
+fn router() -> Router {
+    let mut router = Router::new();
+    for resource in ["users", "posts", "comments", "orders", "invoices"] {
+        router.get(&format!("/api/{}", resource), handler!(ok));
+        router.post(&format!("/api/{}", resource), handler!(ok));
+        router.get(&format!("/api/{}/:id", resource), handler!(ok));
+        router.put(&format!("/api/{}/:id", resource), handler!(ok));
+        router.delete(&format!("/api/{}/:id", resource), handler!(ok));
+    }
+    router.get("/api/users/:id/posts/:postId", handler!(ok));
+    router.get("/assets/*path", handler!(ok));
+    router.use_middleware(LoggingMiddleware::new());
+    router
+}
+
+fn event(method: &str, path: &str) -> Value {
+    json!({
+        "requestContext": {
+            "http": { "method": method },
+            "requestId": "bench-request-id"
+        },
+        "rawPath": path,
+        "headers": {},
+        "body": null
+    })
+}
+
+fn bench_dispatch(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let router = router();
+    let paths = [
+        ("GET", "/api/invoices"),
+        ("PUT", "/api/orders/42"),
+        ("GET", "/api/users/7/posts/99"),
+        ("GET", "/assets/css/app.css"),
+        ("GET", "/missing"),
+    ];
+
+    c.bench_function("dispatch_no_middleware", |b| {
+        b.iter(|| {
+            for (method, path) in paths {
+                runtime.block_on(router.dispatch_no_middleware(event(method, path)));
+            }
+        })
+    });
+
+    c.bench_function("dispatch", |b| {
+        b.iter(|| {
+            for (method, path) in paths {
+                runtime.block_on(router.dispatch(event(method, path)));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_dispatch);
+criterion_main!(benches);
@@ -0,0 +1,92 @@
+//! Request cookies
+//!
+//! Parses the `Cookie` header (API Gateway v2 `cookies` arrays are merged
+//! into it when the request is built) into name/value pairs, and signs and
+//! verifies cookie values with HMAC-SHA256.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Cookies sent with a request, in header order
+///
+/// Names are case-sensitive, as in RFC 6265. If a name appears more than
+/// once, `get` returns the first value and `iter` yields all of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CookieJar {
+    cookies: Vec<(String, String)>,
+}
+
+impl CookieJar {
+    /// Parse a `Cookie` header value such as `session=abc; theme=dark`
+    ///
+    /// Pairs without `=` are skipped and double-quoted values are unquoted.
+    pub fn parse(header: &str) -> Self {
+        let cookies = header
+            .split(';')
+            .filter_map(|pair| {
+                let (name, value) = pair.split_once('=')?;
+                let name = name.trim();
+                if name.is_empty() {
+                    return None;
+                }
+                let value = value.trim();
+                let value = value
+                    .strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .unwrap_or(value);
+                Some((name.to_string(), value.to_string()))
+            })
+            .collect();
+        Self { cookies }
+    }
+
+    /// Value of the first cookie with this name
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.cookies
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// All cookies as `(name, value)` pairs
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.cookies.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    /// Number of cookies
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    /// Whether no cookies were sent
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+}
+
+fn mac(name: &str, value: &str, secret: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key length");
+    // The name is signed too, so a value can't be moved to another cookie
+    mac.update(name.as_bytes());
+    mac.update(b"=");
+    mac.update(value.as_bytes());
+    mac
+}
+
+/// `value` followed by `.` and its base64url signature
+pub(crate) fn sign(name: &str, value: &str, secret: &[u8]) -> String {
+    let signature = mac(name, value, secret).finalize().into_bytes();
+    format!("{}.{}", value, URL_SAFE_NO_PAD.encode(signature))
+}
+
+/// The original value of a signed cookie, if its signature is valid
+///
+/// The signature is compared in constant time.
+pub(crate) fn verify<'a>(name: &str, signed: &'a str, secret: &[u8]) -> Option<&'a str> {
+    let (value, signature) = signed.rsplit_once('.')?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    mac(name, value, secret).verify_slice(&signature).ok()?;
+    Some(value)
+}
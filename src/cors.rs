@@ -1,3 +1,5 @@
+use crate::response::Response;
+
 /// CORS configuration
 #[derive(Debug, Clone)]
 pub struct CorsConfig {
@@ -6,6 +8,8 @@ pub struct CorsConfig {
     pub allow_headers: Vec<String>,
     pub max_age: u32,
     pub allow_credentials: bool,
+    /// Origins allowed by name, each with its own credentials flag
+    pub origins: Vec<(String, bool)>,
 }
 
 impl CorsConfig {
@@ -25,6 +29,7 @@ impl CorsConfig {
             ],
             max_age: 3600,
             allow_credentials: false,
+            origins: Vec::new(),
         }
     }
     
@@ -52,6 +57,86 @@ impl CorsConfig {
         self.allow_credentials = allow;
         self
     }
+    
+    /// Allow a specific origin, sending `Access-Control-Allow-Credentials`
+    /// only if `allow_credentials` is set for it
+    ///
+    /// Requests from a listed origin get that origin echoed back with
+    /// `Vary: Origin`. Once any origin is listed, other origins get no
+    /// `Access-Control-Allow-Origin` unless they match `allow_origin`.
+    pub fn origin(mut self, origin: impl Into<String>, allow_credentials: bool) -> Self {
+        self.origins.push((origin.into(), allow_credentials));
+        self
+    }
+    
+    /// CORS response headers for a request sent from `origin`
+    ///
+    /// Credentials are never sent alongside a `*` origin, since browsers
+    /// reject that combination. With `allow_credentials` set and a `*`
+    /// fallback, unlisted origins get no `Access-Control-Allow-Origin` at all,
+    /// and the same goes for any origin not allowed when `origins` is set.
+    pub fn response_headers(&self, origin: Option<&str>) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        let listed = origin.and_then(|origin| {
+            self.origins
+                .iter()
+                .find(|(allowed, _)| allowed.eq_ignore_ascii_case(origin))
+                .map(|(_, credentials)| (origin, *credentials))
+        });
+        
+        let (allow_origin, credentials) = match listed {
+            Some((origin, credentials)) => {
+                headers.push(("Vary".to_string(), "Origin".to_string()));
+                (Some(origin), credentials)
+            }
+            // A wildcard can't be combined with credentials, so leave the
+            // origin out rather than drop the credentials silently
+            None if self.allow_credentials && self.allow_origin == "*" => (None, false),
+            // An allowlist replaces the fallback, apart from a specific `allow_origin`
+            None if !self.origins.is_empty() => match origin {
+                Some(origin)
+                    if self.allow_origin != "*" && self.allow_origin.eq_ignore_ascii_case(origin) =>
+                {
+                    (Some(self.allow_origin.as_str()), self.allow_credentials)
+                }
+                _ => (None, false),
+            },
+            None => (Some(self.allow_origin.as_str()), self.allow_credentials),
+        };
+        
+        if let Some(allow_origin) = allow_origin {
+            headers.push(("Access-Control-Allow-Origin".to_string(), allow_origin.to_string()));
+        }
+        if credentials {
+            headers.push(("Access-Control-Allow-Credentials".to_string(), "true".to_string()));
+        }
+        headers.push(("Access-Control-Allow-Methods".to_string(), self.allow_methods.join(", ")));
+        headers.push(("Access-Control-Allow-Headers".to_string(), self.allow_headers.join(", ")));
+        headers.push(("Access-Control-Max-Age".to_string(), self.max_age.to_string()));
+        headers
+    }
+    
+    /// Replace the CORS headers of `response` with the ones for `origin`
+    ///
+    /// Overrides the wildcard headers added by `Response::with_cors`.
+    /// Responses built with `Response::without_cors` are returned unchanged.
+    pub fn apply(&self, mut response: Response, origin: Option<&str>) -> Response {
+        if response.cors_disabled {
+            return response;
+        }
+        response.headers.retain(|name, _| {
+            !name.eq_ignore_ascii_case("Access-Control-Allow-Origin")
+                && !name.eq_ignore_ascii_case("Access-Control-Allow-Credentials")
+        });
+        for (name, value) in self.response_headers(origin) {
+            response = if name == "Vary" {
+                response.append_header(name, value)
+            } else {
+                response.header(name, value)
+            };
+        }
+        response
+    }
 }
 
 impl Default for CorsConfig {
@@ -0,0 +1,119 @@
+//! CSV response bodies
+//!
+//! Rows are framed as described in RFC 4180: fields are separated by commas,
+//! records end with CRLF, and fields containing a comma, quote or line break
+//! are quoted with inner quotes doubled.
+
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::error::{Result, RouterError};
+use crate::response::{Response, MAX_RESPONSE_BODY_SIZE};
+
+/// Media type of CSV bodies
+pub const CONTENT_TYPE: &str = "text/csv";
+
+/// Buffered CSV body that refuses to grow past a size limit
+///
+/// Use it when the export is known to be small, or when the router isn't
+/// served with `into_streaming_service`; otherwise prefer
+/// `Response::stream_csv`.
+#[derive(Debug, Clone)]
+pub struct CsvBuilder {
+    body: String,
+    max_size: usize,
+}
+
+impl CsvBuilder {
+    /// Start a body with its header row, limited to `MAX_RESPONSE_BODY_SIZE`
+    pub fn new<I>(header_row: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        Self {
+            body: encode_row(header_row),
+            max_size: MAX_RESPONSE_BODY_SIZE,
+        }
+    }
+
+    /// Largest body, in bytes, that `row` will build
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Append a row, failing if the body would exceed the size limit
+    ///
+    /// The body is left unchanged when the row is rejected.
+    pub fn row<I>(&mut self, fields: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let row = encode_row(fields);
+        if self.body.len() + row.len() > self.max_size {
+            return Err(RouterError::InternalError(format!(
+                "CSV body exceeds {} bytes",
+                self.max_size
+            )));
+        }
+        self.body.push_str(&row);
+        Ok(())
+    }
+
+    /// The body built so far
+    pub fn as_str(&self) -> &str {
+        &self.body
+    }
+
+    /// 200 response carrying the CSV body
+    pub fn into_response(self) -> Response {
+        Response::new(200)
+            .text(self.body)
+            .header("Content-Type", CONTENT_TYPE)
+    }
+}
+
+impl Response {
+    /// 200 streamed CSV response: the header row, then each row as it arrives
+    ///
+    /// See `Response::stream` for how streamed bodies are sent.
+    pub fn stream_csv<H, S>(header_row: H, rows: S) -> Self
+    where
+        H: IntoIterator,
+        H::Item: AsRef<str>,
+        S: Stream + Send + 'static,
+        S::Item: IntoIterator,
+        <S::Item as IntoIterator>::Item: AsRef<str>,
+    {
+        let header = stream::once(futures::future::ready(encode_row(header_row)));
+        let body = header
+            .chain(rows.map(encode_row))
+            .map(bytes::Bytes::from);
+        Response::stream(body).header("Content-Type", CONTENT_TYPE)
+    }
+}
+
+/// One CSV record, terminated by CRLF
+pub fn encode_row<I>(fields: I) -> String
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let mut row = String::new();
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            row.push(',');
+        }
+        let field = field.as_ref();
+        if field.contains([',', '"', '\r', '\n']) {
+            row.push('"');
+            row.push_str(&field.replace('"', "\"\""));
+            row.push('"');
+        } else {
+            row.push_str(field);
+        }
+    }
+    row.push_str("\r\n");
+    row
+}
@@ -0,0 +1,28 @@
+//! Opaque pagination cursors
+//!
+//! A cursor is any serializable value (e.g. a DynamoDB `LastEvaluatedKey`)
+//! encoded as URL-safe base64 JSON so clients can pass it back verbatim.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Result, RouterError};
+
+/// Encode a value into an opaque cursor string
+pub fn encode<T: Serialize>(value: &T) -> Result<String> {
+    let json = serde_json::to_vec(value)?;
+    Ok(URL_SAFE_NO_PAD.encode(json))
+}
+
+/// Decode a cursor produced by `encode`
+///
+/// Malformed cursors produce a `BadRequest` since they come from the client.
+pub fn decode<T: DeserializeOwned>(cursor: &str) -> Result<T> {
+    let invalid = || RouterError::BadRequest("Invalid pagination cursor".to_string());
+    let bytes = URL_SAFE_NO_PAD
+        .decode(cursor.trim().trim_end_matches('='))
+        .map_err(|_| invalid())?;
+    serde_json::from_slice(&bytes).map_err(|_| invalid())
+}
@@ -1,6 +1,15 @@
 use crate::response::Response;
+use serde::Serialize;
+use serde_json::json;
 use thiserror::Error;
 
+/// A single validation failure, located by JSON pointer into the body
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationIssue {
+    pub pointer: String,
+    pub message: String,
+}
+
 /// Router-specific errors
 #[derive(Error, Debug)]
 pub enum RouterError {
@@ -22,11 +31,18 @@ pub enum RouterError {
     #[error("Internal server error: {0}")]
     InternalError(String),
 
+    #[error("Validation failed with {} error(s)", .0.len())]
+    ValidationFailed(Vec<ValidationIssue>),
+
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] serde_json::Error),
 
     #[error("Handler error: {0}")]
     HandlerError(#[from] anyhow::Error),
+
+    /// An application error already converted to its response
+    #[error("Error response with status {}", .0.status_code)]
+    Response(Box<Response>),
 }
 
 // Implement From<&str> for convenience
@@ -44,24 +60,118 @@ impl From<String> for RouterError {
 }
 
 impl RouterError {
+    /// HTTP status code the error maps to
+    ///
+    /// | Variant | Status |
+    /// |---|---|
+    /// | `RouteNotFound` | 404 |
+    /// | `MethodNotAllowed` | 405 |
+    /// | `BadRequest`, `JsonError` | 400 |
+    /// | `Unauthorized` | 401 |
+    /// | `Forbidden` | 403 |
+    /// | `ValidationFailed` | 422 |
+    /// | `InternalError`, `HandlerError` | 500 |
+    /// | `Response` | the response's status |
+    pub fn status_code(&self) -> u16 {
+        match self {
+            RouterError::RouteNotFound { .. } => 404,
+            RouterError::MethodNotAllowed { .. } => 405,
+            RouterError::BadRequest(_) | RouterError::JsonError(_) => 400,
+            RouterError::Unauthorized(_) => 401,
+            RouterError::Forbidden(_) => 403,
+            RouterError::ValidationFailed(_) => 422,
+            RouterError::InternalError(_) | RouterError::HandlerError(_) => 500,
+            RouterError::Response(response) => response.status_code,
+        }
+    }
+
+    /// Machine-readable code for the error, e.g. `route_not_found`
+    pub fn code(&self) -> &'static str {
+        match self {
+            RouterError::RouteNotFound { .. } => "route_not_found",
+            RouterError::MethodNotAllowed { .. } => "method_not_allowed",
+            RouterError::BadRequest(_) => "bad_request",
+            RouterError::Unauthorized(_) => "unauthorized",
+            RouterError::Forbidden(_) => "forbidden",
+            RouterError::InternalError(_) => "internal_error",
+            RouterError::ValidationFailed(_) => "validation_failed",
+            RouterError::JsonError(_) => "invalid_json",
+            RouterError::HandlerError(_) => "handler_error",
+            RouterError::Response(_) => "error_response",
+        }
+    }
+
+    /// Convert into a JSON response, see `to_response`
+    pub fn into_response(self) -> Response {
+        match self {
+            RouterError::Response(response) => *response,
+            err => err.to_response(),
+        }
+    }
+
     /// Convert RouterError to HTTP Response
+    ///
+    /// The body is the standard error body (see `Response::error`) with the
+    /// error's `code` added, and the validation issues under `details` for
+    /// `ValidationFailed`. A `Response` variant is returned as is.
     pub fn to_response(&self) -> Response {
-        match self {
+        let response = match self {
             RouterError::RouteNotFound { method, path } => {
                 Response::not_found(&format!("Route not found: {} {}", method, path))
             }
-            RouterError::MethodNotAllowed { method } => {
-                Response::method_not_allowed(&format!("Method not allowed: {}", method))
-            }
+            RouterError::MethodNotAllowed { method } => Response::method_not_allowed(&[])
+                .with_error_field(
+                    "message",
+                    json!(format!("Method not allowed: {}", method)),
+                ),
             RouterError::BadRequest(msg) => Response::bad_request(msg),
             RouterError::Unauthorized(msg) => Response::unauthorized(msg),
             RouterError::Forbidden(msg) => Response::forbidden(msg),
             RouterError::InternalError(msg) => Response::internal_error(msg),
+            RouterError::ValidationFailed(issues) => {
+                Response::error(422, "Request body failed validation")
+                    .with_error_field("details", json!(issues))
+            }
             RouterError::JsonError(e) => Response::bad_request(&format!("Invalid JSON: {}", e)),
             RouterError::HandlerError(e) => {
                 Response::internal_error(&format!("Handler error: {}", e))
             }
-        }
+            RouterError::Response(response) => return (**response).clone(),
+        };
+        response.with_error_field("code", json!(self.code()))
+    }
+}
+
+/// Conversion of an error into the response sent to the client
+///
+/// Implement this for an application error type so handlers wrapped with
+/// `handler!` can return `Result<Response, MyError>`. Errors are still
+/// reported to `Router::on_error` hooks, as `RouterError::Response`.
+pub trait IntoResponse {
+    fn into_response(self) -> Response;
+
+    /// The error seen by error hooks
+    fn into_router_error(self) -> RouterError
+    where
+        Self: Sized,
+    {
+        RouterError::Response(Box::new(self.into_response()))
+    }
+}
+
+impl IntoResponse for Response {
+    fn into_response(self) -> Response {
+        self
+    }
+}
+
+impl IntoResponse for RouterError {
+    fn into_response(self) -> Response {
+        RouterError::into_response(self)
+    }
+
+    fn into_router_error(self) -> RouterError {
+        self
     }
 }
 
@@ -0,0 +1,71 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use crate::RouterError;
+
+/// IPv4 or IPv6 network in CIDR notation, e.g. `10.0.0.0/8` or `2001:db8::/32`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// Create a CIDR block, returning None if the prefix length is out of range
+    pub fn new(network: IpAddr, prefix_len: u8) -> Option<Self> {
+        let max = match network {
+            IpAddr::V4(_) => 32,
+            IpAddr::V6(_) => 128,
+        };
+        (prefix_len <= max).then_some(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    /// Check whether an address falls inside this block
+    ///
+    /// IPv4 and IPv6 never match each other.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (self.network, addr) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = RouterError;
+
+    /// Parse `addr/prefix`; a bare address is treated as a single-host block
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RouterError::InternalError(format!("Invalid CIDR block: {}", s));
+
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let network: IpAddr = addr.trim().parse().map_err(|_| invalid())?;
+        let prefix_len = match prefix {
+            Some(p) => p.trim().parse().map_err(|_| invalid())?,
+            None if network.is_ipv4() => 32,
+            None => 128,
+        };
+
+        Self::new(network, prefix_len).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
@@ -0,0 +1,92 @@
+//! JSON:API documents
+//!
+//! Builders for the `data` and `errors` top-level members described at
+//! <https://jsonapi.org/format/>, sent with the `application/vnd.api+json`
+//! content type.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::response::{status_reason, Response};
+
+/// Media type of JSON:API documents
+pub const CONTENT_TYPE: &str = "application/vnd.api+json";
+
+/// One entry of a JSON:API `errors` array
+///
+/// Only the members that are set are serialized. `status` is a string, as
+/// the spec requires.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JsonApiError {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<Value>,
+}
+
+impl JsonApiError {
+    /// Error for `status`, titled with its reason phrase
+    pub fn new(status: u16) -> Self {
+        Self {
+            status: status.to_string(),
+            code: None,
+            title: status_reason(status).to_string(),
+            detail: None,
+            source: None,
+        }
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Application-specific error code
+    pub fn code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+
+    /// JSON pointer to the offending member of the request document,
+    /// e.g. `/data/attributes/email`
+    pub fn pointer(mut self, pointer: impl Into<String>) -> Self {
+        self.source = Some(json!({ "pointer": pointer.into() }));
+        self
+    }
+}
+
+impl Response {
+    /// 200 JSON:API document with a single resource object
+    pub fn jsonapi_resource(kind: &str, id: &str, attributes: Value) -> Self {
+        Self::new(200)
+            .json(json!({
+                "data": {
+                    "type": kind,
+                    "id": id,
+                    "attributes": attributes
+                }
+            }))
+            .header("Content-Type", CONTENT_TYPE)
+    }
+
+    /// JSON:API error document with a single error
+    pub fn jsonapi_error(status: u16, title: &str, detail: &str) -> Self {
+        Self::jsonapi_errors(status, &[JsonApiError::new(status).title(title).detail(detail)])
+    }
+
+    /// JSON:API error document listing several errors under one status
+    pub fn jsonapi_errors(status: u16, errors: &[JsonApiError]) -> Self {
+        Self::new(status)
+            .json(json!({ "errors": errors }))
+            .header("Content-Type", CONTENT_TYPE)
+    }
+}
@@ -35,21 +35,39 @@
 //! }
 //! ```
 
+pub mod cookie;
 pub mod cors;
+pub mod csv;
+pub mod cursor;
 pub mod error;
+pub mod ip;
+pub mod jsonapi;
 pub mod matcher;
+pub mod metrics;
 pub mod middleware;
+pub mod multipart;
+pub mod rate_limit;
 pub mod request;
 pub mod response;
 pub mod router;
 
 // Re-export main types
+pub use cookie::CookieJar;
 pub use cors::CorsConfig;
-pub use error::{Result, RouterError};
+pub use csv::CsvBuilder;
+pub use error::{IntoResponse, Result, RouterError, ValidationIssue};
+pub use ip::IpCidr;
+pub use jsonapi::JsonApiError;
 pub use matcher::PathMatcher;
-pub use middleware::{Middleware, Next};
-pub use request::{Context, Request};
-pub use response::Response;
-pub use router::{Handler, HandlerFn, Router};
+pub use metrics::{LatencyHistogram, Percentiles};
+pub use middleware::{Middleware, Next, SkipReason};
+pub use multipart::{MultipartField, MultipartFields};
+pub use rate_limit::RateLimiter;
+pub use request::{BodyConfig, Context, Request};
+pub use response::{Response, MAX_RESPONSE_BODY_SIZE};
+pub use router::{
+    Authorizer, BodySerializer, ErrorHook, Handler, HandlerFn, Interceptor, ResponseStream,
+    RouteGroup, RouteHandle, RouteInfo, RouteMatch, Router, StreamingResponseFuture,
+};
 
 // The handler! macro is already exported via #[macro_export] in router.rs
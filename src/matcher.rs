@@ -3,7 +3,8 @@ use lazy_static::lazy_static;
 use std::collections::HashMap;
 
 lazy_static! {
-    static ref PARAM_REGEX: Regex = Regex::new(r":([a-zA-Z_][a-zA-Z0-9_]*)").unwrap();
+    static ref PARAM_REGEX: Regex = Regex::new(r":([a-zA-Z_][a-zA-Z0-9_]*)|\{([a-zA-Z_][a-zA-Z0-9_]*)\}").unwrap();
+    static ref WILDCARD_REGEX: Regex = Regex::new(r"^([*+])([a-zA-Z_][a-zA-Z0-9_]*)$").unwrap();
 }
 
 /// Path matcher with parameter extraction
@@ -12,31 +13,84 @@ pub struct PathMatcher {
     pattern: String,
     regex: Regex,
     param_names: Vec<String>,
+    specificity: Vec<u8>,
 }
 
 impl PathMatcher {
     /// Create a new PathMatcher from a route pattern
     /// Supports Express-like patterns: /api/users/:userId/posts/:postId
+    /// and OpenAPI-style braces: /api/users/{userId}/posts/{postId}.
+    /// Both syntaxes capture the same way and may be mixed in one pattern.
+    ///
+    /// The final segment may be a wildcard capturing the rest of the path,
+    /// slashes included:
+    /// - `*name` also matches an empty rest, so `/docs/*path` matches `/docs`,
+    ///   `/docs/` and `/docs/a/b`
+    /// - `+name` requires at least one character, so `/docs/+path` does not
+    ///   match `/docs` or `/docs/`
+    ///
+    /// # Panics
+    /// Panics if a wildcard appears anywhere but the final segment.
     pub fn new(pattern: &str) -> Self {
         let mut param_names = Vec::new();
         
-        // Extract parameter names
-        for cap in PARAM_REGEX.captures_iter(pattern) {
-            param_names.push(cap[1].to_string());
+        // Split off a trailing wildcard segment
+        let (prefix, wildcard) = match pattern.rsplit_once('/') {
+            Some((prefix, last)) => match WILDCARD_REGEX.captures(last) {
+                Some(cap) => (prefix, Some((cap[1].to_string(), cap[2].to_string()))),
+                None => (pattern, None),
+            },
+            None => (pattern, None),
+        };
+        
+        if prefix.split('/').any(|segment| WILDCARD_REGEX.is_match(segment)) {
+            panic!("Wildcard must be the final segment of route pattern: {}", pattern);
+        }
+        
+        // Convert Express-style pattern to regex, escaping literal text
+        let mut regex_pattern = String::from("^");
+        let mut last = 0;
+        for cap in PARAM_REGEX.captures_iter(prefix) {
+            let m = cap.get(0).unwrap();
+            regex_pattern.push_str(&regex::escape(&prefix[last..m.start()]));
+            regex_pattern.push_str(r"([^/]+)");
+            let name = cap.get(1).or_else(|| cap.get(2)).unwrap();
+            param_names.push(name.as_str().to_string());
+            last = m.end();
         }
+        regex_pattern.push_str(&regex::escape(&prefix[last..]));
         
-        // Convert Express-style pattern to regex
-        let regex_pattern = PARAM_REGEX
-            .replace_all(pattern, r"([^/]+)")
-            .replace("/", r"/");
+        if let Some((kind, name)) = wildcard {
+            regex_pattern.push_str(if kind == "*" { r"(?:/(.*))?" } else { r"/(.+)" });
+            param_names.push(name);
+        }
         
-        let regex_pattern = format!("^{}$", regex_pattern);
+        regex_pattern.push('$');
         let regex = Regex::new(&regex_pattern).unwrap();
         
+        // Rank each segment: static, then parameter, then wildcard. The end of
+        // the pattern ranks above all of them, so `/docs` beats `/docs/*path`
+        // for the path `/docs`.
+        let mut specificity: Vec<u8> = pattern
+            .split('/')
+            .skip(1)
+            .map(|segment| {
+                if WILDCARD_REGEX.is_match(segment) {
+                    0
+                } else if PARAM_REGEX.is_match(segment) {
+                    1
+                } else {
+                    2
+                }
+            })
+            .collect();
+        specificity.push(3);
+        
         Self {
             pattern: pattern.to_string(),
             regex,
             param_names,
+            specificity,
         }
     }
     
@@ -46,8 +100,10 @@ impl PathMatcher {
             self.param_names
                 .iter()
                 .enumerate()
-                .filter_map(|(i, name)| {
-                    captures.get(i + 1).map(|m| (name.clone(), m.as_str().to_string()))
+                .map(|(i, name)| {
+                    // An empty `*` wildcard doesn't participate in the match
+                    let value = captures.get(i + 1).map(|m| m.as_str()).unwrap_or("");
+                    (name.clone(), value.to_string())
                 })
                 .collect()
         })
@@ -57,6 +113,68 @@ impl PathMatcher {
     pub fn pattern(&self) -> &str {
         &self.pattern
     }
+    
+    /// Segment-by-segment ranking used to order overlapping routes
+    ///
+    /// Compares lexicographically: a higher value at the first differing
+    /// segment means a more specific pattern.
+    pub(crate) fn specificity(&self) -> &[u8] {
+        &self.specificity
+    }
+    
+    /// Names of the path parameters, in pattern order
+    pub fn param_names(&self) -> &[String] {
+        &self.param_names
+    }
+}
+
+/// Force initialization of the shared pattern-parsing regexes
+pub(crate) fn warm_up_statics() {
+    lazy_static::initialize(&PARAM_REGEX);
+    lazy_static::initialize(&WILDCARD_REGEX);
+}
+
+/// Percent-decode a request path, rejecting sequences that aren't valid UTF-8
+///
+/// Encoded slashes (`%2F`) are kept encoded when `keep_slashes` is set so that
+/// decoding never introduces new path segments. Encoded percent signs (`%25`)
+/// are kept too, so a segment can be decoded once more without any escape
+/// being decoded twice.
+pub(crate) fn percent_decode(input: &str, keep_slashes: bool) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                if !(keep_slashes && (byte == b'/' || byte == b'%')) {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+/// Percent-encode a query string component, keeping only RFC 3986 unreserved characters
+pub(crate) fn percent_encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
 }
 
 #[cfg(test)]
@@ -0,0 +1,126 @@
+use std::time::Duration;
+
+/// Number of buckets kept per histogram
+///
+/// Bucket `i` covers latencies up to `GROWTH^i` microseconds, so 192 buckets
+/// reach well past a minute while keeping each histogram a fixed size.
+const BUCKET_COUNT: usize = 192;
+
+/// Relative width of each bucket (10%)
+const GROWTH: f64 = 1.1;
+
+/// Latency percentiles computed from a histogram
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Percentiles {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+}
+
+/// Fixed-size, log-bucketed latency histogram
+///
+/// Each recorded value is rounded up to its bucket's upper bound, so reported
+/// percentiles overestimate the true value by at most 10%.
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    buckets: [u64; BUCKET_COUNT],
+    count: u64,
+}
+
+impl LatencyHistogram {
+    /// Create an empty histogram
+    pub fn new() -> Self {
+        Self {
+            buckets: [0; BUCKET_COUNT],
+            count: 0,
+        }
+    }
+
+    /// Record a single latency sample
+    pub fn record(&mut self, latency: Duration) {
+        let micros = latency.as_micros().min(u64::MAX as u128) as u64;
+        self.buckets[bucket_for(micros)] += 1;
+        self.count += 1;
+    }
+
+    /// Number of recorded samples
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Approximate latency at quantile `q` (0.0..=1.0)
+    pub fn percentile(&self, q: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return Some(bucket_upper_bound(i));
+            }
+        }
+
+        Some(bucket_upper_bound(BUCKET_COUNT - 1))
+    }
+
+    /// p50/p90/p99 summary, or None when nothing has been recorded
+    pub fn percentiles(&self) -> Option<Percentiles> {
+        Some(Percentiles {
+            p50: self.percentile(0.50)?,
+            p90: self.percentile(0.90)?,
+            p99: self.percentile(0.99)?,
+        })
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn bucket_for(micros: u64) -> usize {
+    if micros <= 1 {
+        return 0;
+    }
+    let index = ((micros as f64).ln() / GROWTH.ln()).ceil() as usize;
+    index.min(BUCKET_COUNT - 1)
+}
+
+fn bucket_upper_bound(index: usize) -> Duration {
+    Duration::from_micros(GROWTH.powi(index as i32).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn within_ten_percent(actual: Duration, expected: Duration) -> bool {
+        let actual = actual.as_micros() as f64;
+        let expected = expected.as_micros() as f64;
+        actual >= expected && actual <= expected * 1.1
+    }
+
+    #[test]
+    fn test_empty_histogram() {
+        let histogram = LatencyHistogram::new();
+        assert_eq!(histogram.count(), 0);
+        assert!(histogram.percentiles().is_none());
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let mut histogram = LatencyHistogram::new();
+        for ms in 1..=100 {
+            histogram.record(Duration::from_millis(ms));
+        }
+
+        let percentiles = histogram.percentiles().unwrap();
+        assert!(within_ten_percent(percentiles.p50, Duration::from_millis(50)));
+        assert!(within_ten_percent(percentiles.p90, Duration::from_millis(90)));
+        assert!(within_ten_percent(percentiles.p99, Duration::from_millis(99)));
+    }
+}
@@ -1,10 +1,49 @@
 use async_trait::async_trait;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
 use lambda_runtime::Error;
-use crate::{Request, Response};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{Instrument, Level};
+use crate::cors::CorsConfig;
+use crate::matcher::percent_encode;
+use crate::{Context, Request, Response, RouterError};
 
 /// Next function type for middleware chain
 pub type Next = Box<dyn Fn(Request) -> futures::future::BoxFuture<'static, Result<Response, Error>> + Send + Sync>;
 
+/// Why a middleware answered without calling the rest of the chain
+///
+/// The request, and its `Context`, are moved into `next`, so outer
+/// middleware can't see annotations made on them. The reason travels on the
+/// response instead: set it with `Response::short_circuit` and read it with
+/// `Response::short_circuit_reason`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// Credentials missing or invalid
+    Unauthorized,
+    /// Authenticated but not permitted
+    Forbidden,
+    /// Client exceeded a rate limit
+    RateLimited,
+    /// Request failed validation, e.g. missing required headers
+    InvalidRequest,
+    /// Any other reason
+    Custom(String),
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::Unauthorized => f.write_str("unauthorized"),
+            SkipReason::Forbidden => f.write_str("forbidden"),
+            SkipReason::RateLimited => f.write_str("rate_limited"),
+            SkipReason::InvalidRequest => f.write_str("invalid_request"),
+            SkipReason::Custom(reason) => f.write_str(reason),
+        }
+    }
+}
+
 /// Middleware trait
 #[async_trait]
 pub trait Middleware: Send + Sync {
@@ -39,53 +78,699 @@ where
     }
 }
 
-/// Logging middleware
-pub struct LoggingMiddleware;
+/// Expand a `tracing` macro for a level chosen at runtime
+///
+/// `tracing` needs the level of a callsite at compile time, so each level
+/// gets its own callsite.
+macro_rules! at_level {
+    ($level:expr, $error:ident, $warn:ident, $info:ident, $debug:ident, $trace:ident, $($args:tt)+) => {
+        if $level == Level::ERROR {
+            tracing::$error!($($args)+)
+        } else if $level == Level::WARN {
+            tracing::$warn!($($args)+)
+        } else if $level == Level::INFO {
+            tracing::$info!($($args)+)
+        } else if $level == Level::DEBUG {
+            tracing::$debug!($($args)+)
+        } else {
+            tracing::$trace!($($args)+)
+        }
+    };
+}
+
+/// Middleware logging each request as a structured `tracing` event
+///
+/// Everything downstream runs inside a `request` span carrying the request
+/// id and X-Ray trace id. When the response is ready, one event records the
+/// method, path, matched route pattern, status, elapsed milliseconds, and
+/// any short-circuit reason. Handler errors are logged at `ERROR`
+/// regardless of the configured level.
+pub struct LoggingMiddleware {
+    level: Level,
+    include_query: bool,
+}
+
+impl LoggingMiddleware {
+    /// Log at `INFO`, without query strings
+    pub fn new() -> Self {
+        Self {
+            level: Level::INFO,
+            include_query: false,
+        }
+    }
+
+    /// Level of the span and of the completion event
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Log the path with its query string; off by default since queries
+    /// may carry tokens or personal data
+    pub fn include_query(mut self, include: bool) -> Self {
+        self.include_query = include;
+        self
+    }
+}
+
+impl Default for LoggingMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[async_trait]
 impl Middleware for LoggingMiddleware {
     async fn handle(&self, req: Request, next: Next) -> Result<Response, Error> {
-        println!("→ {} {}", req.method, req.path);
+        let level = self.level;
+        let request_id = req.context.request_id.clone();
+        let trace_id = req.context.trace_id.clone();
+        let span = at_level!(
+            level, error_span, warn_span, info_span, debug_span, trace_span,
+            "request", request_id = %request_id, trace_id = trace_id.as_deref()
+        );
+
+        let method = req.method.clone();
+        let path = if self.include_query && !req.query_params.is_empty() {
+            let mut pairs: Vec<_> = req.query_params.iter().collect();
+            pairs.sort();
+            let query: Vec<String> = pairs.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+            format!("{}?{}", req.path, query.join("&"))
+        } else {
+            req.path.clone()
+        };
+        let route = req.matched_route.clone();
+        let started = Instant::now();
+
+        let result = next(req).instrument(span.clone()).await;
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let _entered = span.enter();
+
+        match &result {
+            Ok(response) => at_level!(
+                level, error, warn, info, debug, trace,
+                method = %method,
+                path = %path,
+                route = route.as_deref(),
+                status = response.status_code,
+                elapsed_ms,
+                short_circuit = response.short_circuit_reason().map(|r| r.to_string()).as_deref(),
+                "request completed"
+            ),
+            Err(e) => tracing::error!(
+                method = %method,
+                path = %path,
+                route = route.as_deref(),
+                elapsed_ms,
+                error = %e,
+                "request failed"
+            ),
+        }
+
+        result
+    }
+}
+
+/// Middleware that rejects requests missing any of the required headers
+///
+/// Only the presence of each header is checked, not its value. Requests
+/// missing one or more headers get a 400 listing every missing name.
+pub struct RequireHeadersMiddleware {
+    headers: Vec<String>,
+}
+
+impl RequireHeadersMiddleware {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for RequireHeadersMiddleware {
+    async fn handle(&self, req: Request, next: Next) -> Result<Response, Error> {
+        let missing: Vec<&str> = self
+            .headers
+            .iter()
+            .filter(|name| req.header(name).is_none())
+            .map(|name| name.as_str())
+            .collect();
+
+        if !missing.is_empty() {
+            return Ok(Response::bad_request(&format!(
+                "Missing required headers: {}",
+                missing.join(", ")
+            ))
+            .short_circuit(SkipReason::InvalidRequest));
+        }
+
+        next(req).await
+    }
+}
+
+/// Claims of a verified JWT, attached to the `Context` by `JwtAuthMiddleware`
+///
+/// Read them with `ctx.get_extension::<JwtClaims>()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JwtClaims(pub Value);
+
+/// Maps verified JWT claims onto the request `Context`
+pub type ClaimsMapper = Arc<dyn Fn(&Value, &mut Context) + Send + Sync>;
+
+/// Middleware that verifies `Authorization: Bearer` JWTs
+///
+/// On success the token is stored in `Context.auth_token`, the claims are
+/// attached as a `JwtClaims` extension, and the claims mapper runs; by
+/// default it copies `sub` to `user_id` and `email` to `email`. Requests
+/// without a token, or with one that is expired, badly signed or otherwise
+/// invalid, get a 401 without reaching the handler.
+pub struct JwtAuthMiddleware {
+    key: DecodingKey,
+    validation: Validation,
+    map_claims: ClaimsMapper,
+}
+
+impl JwtAuthMiddleware {
+    /// Verify HS256 tokens signed with a shared secret
+    pub fn hmac(secret: &[u8]) -> Self {
+        Self::new(DecodingKey::from_secret(secret), Algorithm::HS256)
+    }
+
+    /// Verify RS256 tokens against a PEM-encoded RSA public key
+    pub fn rsa_pem(public_key: &[u8]) -> Result<Self, RouterError> {
+        let key = DecodingKey::from_rsa_pem(public_key)
+            .map_err(|e| RouterError::InternalError(format!("Invalid RSA public key: {}", e)))?;
+        Ok(Self::new(key, Algorithm::RS256))
+    }
+
+    fn new(key: DecodingKey, algorithm: Algorithm) -> Self {
+        Self {
+            key,
+            validation: Validation::new(algorithm),
+            map_claims: Arc::new(default_claims_mapper),
+        }
+    }
+
+    /// Only accept tokens whose `iss` is `issuer`
+    pub fn issuer(mut self, issuer: &str) -> Self {
+        self.validation.set_issuer(&[issuer]);
+        self
+    }
+
+    /// Only accept tokens whose `aud` includes `audience`
+    pub fn audience(mut self, audience: &str) -> Self {
+        self.validation.set_audience(&[audience]);
+        self
+    }
+
+    /// Seconds of clock skew tolerated when checking `exp` and `nbf`
+    pub fn leeway(mut self, seconds: u64) -> Self {
+        self.validation.leeway = seconds;
+        self
+    }
+
+    /// Replace the default `sub`/`email` mapping of claims onto the context
+    pub fn map_claims<F>(mut self, mapper: F) -> Self
+    where
+        F: Fn(&Value, &mut Context) + Send + Sync + 'static,
+    {
+        self.map_claims = Arc::new(mapper);
+        self
+    }
+}
+
+fn default_claims_mapper(claims: &Value, context: &mut Context) {
+    context.user_id = claims["sub"].as_str().map(|s| s.to_string());
+    context.email = claims["email"].as_str().map(|s| s.to_string());
+}
+
+#[async_trait]
+impl Middleware for JwtAuthMiddleware {
+    async fn handle(&self, mut req: Request, next: Next) -> Result<Response, Error> {
+        let unauthorized = |message: &str| {
+            Ok(Response::unauthorized(message).short_circuit(SkipReason::Unauthorized))
+        };
+
+        let Some(token) = req.bearer_token().map(|t| t.to_string()) else {
+            return unauthorized("Missing bearer token");
+        };
+
+        let claims = match jsonwebtoken::decode::<Value>(&token, &self.key, &self.validation) {
+            Ok(data) => data.claims,
+            Err(e) => {
+                return match e.kind() {
+                    jsonwebtoken::errors::ErrorKind::ExpiredSignature => {
+                        unauthorized("Token has expired")
+                    }
+                    _ => unauthorized("Invalid token"),
+                }
+            }
+        };
+
+        req.context.auth_token = Some(token);
+        (self.map_claims)(&claims, &mut req.context);
+        req.context.insert_extension(JwtClaims(claims));
+
+        next(req).await
+    }
+}
+
+/// Hash algorithm used by `ETagMiddleware`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ETagAlgorithm {
+    /// SHA-1 digest of the body
+    Sha1,
+    /// 64-bit FNV-1a hash, cheaper but with weaker collision resistance
+    Fnv1a,
+}
+
+/// Middleware that sets an ETag on 200 responses and answers `If-None-Match`
+///
+/// When the request's `If-None-Match` lists the computed tag (or `*`), the
+/// body is dropped and a 304 Not Modified is returned instead.
+pub struct ETagMiddleware {
+    algorithm: ETagAlgorithm,
+    weak: bool,
+}
+
+impl ETagMiddleware {
+    pub fn new() -> Self {
+        Self {
+            algorithm: ETagAlgorithm::Sha1,
+            weak: false,
+        }
+    }
+    
+    pub fn algorithm(mut self, algorithm: ETagAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+    
+    /// Emit weak (`W/"..."`) instead of strong tags
+    pub fn weak(mut self, weak: bool) -> Self {
+        self.weak = weak;
+        self
+    }
+    
+    /// Compute the ETag header value for a body
+    pub fn compute(&self, body: &str) -> String {
+        let hash = match self.algorithm {
+            ETagAlgorithm::Sha1 => {
+                use sha1::{Digest, Sha1};
+                Sha1::digest(body.as_bytes())
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>()
+            }
+            ETagAlgorithm::Fnv1a => {
+                let hash = body.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+                    (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+                });
+                format!("{:016x}", hash)
+            }
+        };
+        
+        if self.weak {
+            format!("W/\"{}\"", hash)
+        } else {
+            format!("\"{}\"", hash)
+        }
+    }
+}
+
+impl Default for ETagMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Weak comparison of an `If-None-Match` header against a tag
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let strip = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = strip(etag);
+    if_none_match
+        .split(',')
+        .any(|candidate| candidate.trim() == "*" || strip(candidate) == etag)
+}
+
+#[async_trait]
+impl Middleware for ETagMiddleware {
+    async fn handle(&self, req: Request, next: Next) -> Result<Response, Error> {
+        let if_none_match = req.header("If-None-Match").cloned();
         let response = next(req).await?;
-        println!("← {}", response.status_code);
+        
+        if response.status_code != 200 {
+            return Ok(response);
+        }
+        
+        let etag = self.compute(&response.body);
+        let mut response = response.header("ETag", etag.clone());
+        
+        if if_none_match.is_some_and(|header| etag_matches(&header, &etag)) {
+            response.status_code = 304;
+            response.body.clear();
+            response.headers.remove("Content-Type");
+        }
+        
         Ok(response)
     }
 }
 
+/// Content coding applied by `CompressionMiddleware`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// Brotli, only with the `brotli` feature
+    #[cfg(feature = "brotli")]
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    /// `Content-Encoding` token
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "brotli")]
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+    
+    fn compress(&self, body: &[u8]) -> std::io::Result<Vec<u8>> {
+        use std::io::Write;
+        
+        match self {
+            #[cfg(feature = "brotli")]
+            Encoding::Brotli => {
+                let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+                encoder.write_all(body)?;
+                Ok(encoder.into_inner())
+            }
+            Encoding::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(body)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Pick the encoding for an `Accept-Encoding` header, preferring `br` over `gzip`
+///
+/// Codings listed with `q=0` are refused; `*` accepts any coding not listed.
+pub fn negotiate_encoding(accept_encoding: &str) -> Option<Encoding> {
+    let quality = |coding: &str| -> Option<f32> {
+        let mut wildcard = None;
+        for item in accept_encoding.split(',') {
+            let mut params = item.split(';');
+            let name = params.next().unwrap_or("").trim();
+            let q = params
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if name.eq_ignore_ascii_case(coding) {
+                return Some(q);
+            }
+            if name == "*" {
+                wildcard = Some(q);
+            }
+        }
+        wildcard
+    };
+    let accepts = |coding: &str| quality(coding).is_some_and(|q| q > 0.0);
+    
+    #[cfg(feature = "brotli")]
+    if accepts("br") {
+        return Some(Encoding::Brotli);
+    }
+    accepts("gzip").then_some(Encoding::Gzip)
+}
+
+/// Middleware that compresses response bodies for clients that accept it
+///
+/// Uses `br` when the `brotli` feature is enabled and the client advertises
+/// it, and `gzip` otherwise. Compressed bodies are base64-encoded, as API
+/// Gateway requires for binary payloads. Bodies under `min_size`, bodies
+/// that are already encoded or base64, and streamed responses are sent as is.
+pub struct CompressionMiddleware {
+    min_size: usize,
+}
+
+impl CompressionMiddleware {
+    pub fn new() -> Self {
+        Self { min_size: 1024 }
+    }
+    
+    /// Smallest body, in bytes, worth compressing (1 KB by default)
+    pub fn min_size(mut self, bytes: usize) -> Self {
+        self.min_size = bytes;
+        self
+    }
+}
+
+impl Default for CompressionMiddleware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Middleware for CompressionMiddleware {
+    async fn handle(&self, req: Request, next: Next) -> Result<Response, Error> {
+        use base64::Engine;
+        
+        let encoding = req
+            .header("Accept-Encoding")
+            .and_then(|accept| negotiate_encoding(accept));
+        let response = next(req).await?;
+        
+        let already_encoded = response
+            .headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case("Content-Encoding"));
+        if response.is_streaming()
+            || response.is_base64_encoded
+            || already_encoded
+            || response.body.len() < self.min_size
+        {
+            return Ok(response);
+        }
+        
+        let mut response = response.append_header("Vary", "Accept-Encoding");
+        let Some(encoding) = encoding else {
+            return Ok(response);
+        };
+        
+        let compressed = encoding.compress(response.body.as_bytes())?;
+        response.body = base64::engine::general_purpose::STANDARD.encode(compressed);
+        response.is_base64_encoded = true;
+        Ok(response.header("Content-Encoding", encoding.as_str()))
+    }
+}
+
+/// Middleware that aborts handlers running longer than a fixed duration
+///
+/// Returns a 504 by default; use `on_timeout` to customize the response.
+pub struct TimeoutMiddleware {
+    duration: Duration,
+    on_timeout: Box<dyn Fn() -> Response + Send + Sync>,
+}
+
+impl TimeoutMiddleware {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            on_timeout: Box::new(|| Response::gateway_timeout("Request timed out")),
+        }
+    }
+    
+    /// Build the response returned when the handler times out
+    pub fn on_timeout<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> Response + Send + Sync + 'static,
+    {
+        self.on_timeout = Box::new(f);
+        self
+    }
+}
+
+#[async_trait]
+impl Middleware for TimeoutMiddleware {
+    async fn handle(&self, req: Request, next: Next) -> Result<Response, Error> {
+        match tokio::time::timeout(self.duration, next(req)).await {
+            Ok(result) => result,
+            Err(_) => Ok((self.on_timeout)()),
+        }
+    }
+}
+
+/// Middleware that re-invokes the downstream chain on transient failures
+///
+/// Retries when the chain returns an error or a response matching the retry
+/// predicate (503 by default), waiting `backoff * 2^attempt` between attempts.
+/// Each attempt runs on a clone of the original request.
+pub struct RetryMiddleware {
+    max_retries: u32,
+    backoff: Duration,
+    retry_if: Box<dyn Fn(&Response) -> bool + Send + Sync>,
+}
+
+impl RetryMiddleware {
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            backoff: Duration::from_millis(50),
+            retry_if: Box::new(|response| response.status_code == 503),
+        }
+    }
+    
+    /// Base delay before the first retry, doubled on each further attempt
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+    
+    /// Decide which responses are retryable
+    pub fn retry_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Response) -> bool + Send + Sync + 'static,
+    {
+        self.retry_if = Box::new(predicate);
+        self
+    }
+}
+
+#[async_trait]
+impl Middleware for RetryMiddleware {
+    async fn handle(&self, req: Request, next: Next) -> Result<Response, Error> {
+        let mut attempt = 0;
+        loop {
+            let result = next(req.clone()).await;
+            let retryable = match &result {
+                Ok(response) => (self.retry_if)(response),
+                Err(_) => true,
+            };
+            
+            if !retryable || attempt >= self.max_retries {
+                return result;
+            }
+            
+            tokio::time::sleep(self.backoff * 2u32.saturating_pow(attempt)).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// Middleware that adds RFC 5988 `Link` headers to paginated responses
+///
+/// Reads `page`, `limit` and `total` from the JSON body, either at the top
+/// level or inside a `pagination` object, and emits `first`, `prev`, `next`
+/// and `last` links built from the request URL. Responses without that
+/// metadata pass through untouched.
+pub struct PaginationLinkMiddleware;
+
+impl PaginationLinkMiddleware {
+    /// Build the `Link` header value for a request URL and pagination metadata
+    fn link_header(base: &str, query: &[(String, String)], page: u64, limit: u64, total: u64) -> String {
+        let last = total.div_ceil(limit).max(1);
+        let url = |page: u64| {
+            let mut params: Vec<String> = query
+                .iter()
+                .filter(|(k, _)| k != "page" && k != "limit")
+                .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+                .collect();
+            params.push(format!("page={}", page));
+            params.push(format!("limit={}", limit));
+            format!("<{}?{}>", base, params.join("&"))
+        };
+        
+        let mut links = vec![format!("{}; rel=\"first\"", url(1))];
+        if page > 1 {
+            links.push(format!("{}; rel=\"prev\"", url(page - 1)));
+        }
+        if page < last {
+            links.push(format!("{}; rel=\"next\"", url(page + 1)));
+        }
+        links.push(format!("{}; rel=\"last\"", url(last)));
+        links.join(", ")
+    }
+}
+
+#[async_trait]
+impl Middleware for PaginationLinkMiddleware {
+    async fn handle(&self, req: Request, next: Next) -> Result<Response, Error> {
+        let full_url = req.full_url();
+        let base = full_url.split('?').next().unwrap_or(&full_url).to_string();
+        let mut query: Vec<(String, String)> = req
+            .query_params
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        query.sort();
+        
+        let response = next(req).await?;
+        
+        let Ok(body) = serde_json::from_str::<serde_json::Value>(&response.body) else {
+            return Ok(response);
+        };
+        let meta = if body.get("pagination").is_some() {
+            &body["pagination"]
+        } else {
+            &body
+        };
+        let field = |name: &str| meta.get(name).and_then(|v| v.as_u64());
+        
+        match (field("page"), field("limit"), field("total")) {
+            (Some(page), Some(limit), Some(total)) if page > 0 && limit > 0 => {
+                let link = Self::link_header(&base, &query, page, limit, total);
+                Ok(response.header("Link", link))
+            }
+            _ => Ok(response),
+        }
+    }
+}
+
 /// CORS middleware
 pub struct CorsMiddleware {
-    allow_origin: String,
-    allow_methods: String,
-    allow_headers: String,
-    max_age: String,
+    config: CorsConfig,
 }
 
 impl CorsMiddleware {
     pub fn new() -> Self {
-        Self {
-            allow_origin: "*".to_string(),
-            allow_methods: "GET, POST, PUT, DELETE, OPTIONS".to_string(),
-            allow_headers: "Content-Type, Authorization".to_string(),
-            max_age: "3600".to_string(),
-        }
+        Self::from_config(CorsConfig::new())
+    }
+    
+    /// Build the middleware from a `CorsConfig`, e.g. one with per-origin credentials
+    pub fn from_config(config: CorsConfig) -> Self {
+        Self { config }
     }
     
     pub fn allow_origin(mut self, origin: impl Into<String>) -> Self {
-        self.allow_origin = origin.into();
+        self.config.allow_origin = origin.into();
         self
     }
     
     pub fn allow_methods(mut self, methods: impl Into<String>) -> Self {
-        self.allow_methods = methods.into();
+        self.config.allow_methods = split_list(&methods.into());
         self
     }
     
     pub fn allow_headers(mut self, headers: impl Into<String>) -> Self {
-        self.allow_headers = headers.into();
+        self.config.allow_headers = split_list(&headers.into());
         self
     }
 }
 
+/// Split a comma-separated header value into its items
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
 impl Default for CorsMiddleware {
     fn default() -> Self {
         Self::new()
@@ -95,19 +780,16 @@ impl Default for CorsMiddleware {
 #[async_trait]
 impl Middleware for CorsMiddleware {
     async fn handle(&self, req: Request, next: Next) -> Result<Response, Error> {
+        let origin = req.origin().map(|o| o.to_string());
+        
         // Handle preflight
-        if req.is_preflight() {
-            return Ok(Response::cors_preflight());
-        }
+        let response = if req.is_preflight() {
+            Response::cors_preflight()
+        } else {
+            next(req).await?
+        };
         
         // Add CORS headers to response
-        let mut response = next(req).await?;
-        response = response
-            .header("Access-Control-Allow-Origin", &self.allow_origin)
-            .header("Access-Control-Allow-Methods", &self.allow_methods)
-            .header("Access-Control-Allow-Headers", &self.allow_headers)
-            .header("Access-Control-Max-Age", &self.max_age);
-        
-        Ok(response)
+        Ok(self.config.apply(response, origin.as_deref()))
     }
 }
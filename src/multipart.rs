@@ -0,0 +1,210 @@
+//! `multipart/form-data` request bodies
+//!
+//! Parts are parsed one at a time as the iterator advances, and each field
+//! borrows its content from the request body instead of copying it.
+
+use crate::error::{Result, RouterError};
+
+/// One part of a `multipart/form-data` body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultipartField<'a> {
+    name: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    data: &'a [u8],
+}
+
+impl<'a> MultipartField<'a> {
+    /// Form field name from `Content-Disposition`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Uploaded file name, if the part is a file
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// The part's own `Content-Type`, if it sent one
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Raw content of the part
+    pub fn bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Content as a string, or `None` if it isn't valid UTF-8
+    pub fn text(&self) -> Option<&'a str> {
+        std::str::from_utf8(self.data).ok()
+    }
+}
+
+/// Iterator over the parts of a multipart body, returned by `Request::multipart_fields`
+///
+/// Yields an error and then stops if the body is malformed.
+#[derive(Debug)]
+pub struct MultipartFields<'a> {
+    body: &'a [u8],
+    delimiter: Vec<u8>,
+    // Offset just past the last delimiter seen, or `None` before the first one
+    pos: Option<usize>,
+    state: State,
+}
+
+#[derive(Debug)]
+enum State {
+    Active,
+    Failed(String),
+    Done,
+}
+
+impl<'a> MultipartFields<'a> {
+    /// Iterate the parts of `body` for a request with this `Content-Type`
+    ///
+    /// Fails on the first call to `next` if the content type isn't
+    /// `multipart/form-data` with a `boundary` parameter.
+    pub fn new(content_type: Option<&str>, body: &'a [u8]) -> Self {
+        let state = match content_type.map(boundary) {
+            Some(Ok(boundary)) => {
+                return Self {
+                    body,
+                    delimiter: format!("--{}", boundary).into_bytes(),
+                    pos: None,
+                    state: State::Active,
+                }
+            }
+            Some(Err(message)) => State::Failed(message),
+            None => State::Failed(
+                "Missing Content-Type, expected multipart/form-data".to_string(),
+            ),
+        };
+        Self {
+            body,
+            delimiter: Vec::new(),
+            pos: None,
+            state,
+        }
+    }
+
+    fn next_field(&mut self) -> std::result::Result<Option<MultipartField<'a>>, String> {
+        let start = match self.pos {
+            Some(pos) => pos,
+            None => {
+                let first = find(self.body, &self.delimiter)
+                    .ok_or_else(|| "Multipart body has no boundary delimiter".to_string())?;
+                first + self.delimiter.len()
+            }
+        };
+
+        let rest = &self.body[start..];
+        if rest.starts_with(b"--") {
+            return Ok(None);
+        }
+        let rest = rest
+            .strip_prefix(b"\r\n")
+            .ok_or_else(|| "Malformed multipart boundary line".to_string())?;
+        let part_start = self.body.len() - rest.len();
+
+        let headers_end = find(rest, b"\r\n\r\n")
+            .ok_or_else(|| "Multipart part headers are not terminated".to_string())?;
+        let headers = std::str::from_utf8(&rest[..headers_end])
+            .map_err(|_| "Multipart part headers are not valid UTF-8".to_string())?;
+        let content = &rest[headers_end + 4..];
+
+        let mut closing = b"\r\n".to_vec();
+        closing.extend_from_slice(&self.delimiter);
+        let data_len = find(content, &closing)
+            .ok_or_else(|| "Multipart body is missing its closing boundary".to_string())?;
+        let data = &content[..data_len];
+
+        let content_start = part_start + headers_end + 4;
+        self.pos = Some(content_start + data_len + closing.len());
+
+        let mut name = None;
+        let mut filename = None;
+        let mut content_type = None;
+        for line in headers.split("\r\n") {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if key.trim().eq_ignore_ascii_case("Content-Disposition") {
+                name = disposition_param(value, "name");
+                filename = disposition_param(value, "filename");
+            } else if key.trim().eq_ignore_ascii_case("Content-Type") {
+                content_type = Some(value.to_string());
+            }
+        }
+
+        let name = name.ok_or_else(|| "Multipart part has no field name".to_string())?;
+        Ok(Some(MultipartField {
+            name,
+            filename,
+            content_type,
+            data,
+        }))
+    }
+}
+
+impl<'a> Iterator for MultipartFields<'a> {
+    type Item = Result<MultipartField<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match std::mem::replace(&mut self.state, State::Done) {
+            State::Done => None,
+            State::Failed(message) => Some(Err(RouterError::BadRequest(message))),
+            State::Active => match self.next_field() {
+                Ok(Some(field)) => {
+                    self.state = State::Active;
+                    Some(Ok(field))
+                }
+                Ok(None) => None,
+                Err(message) => Some(Err(RouterError::BadRequest(message))),
+            },
+        }
+    }
+}
+
+/// Boundary parameter of a `multipart/form-data` content type
+fn boundary(content_type: &str) -> std::result::Result<String, String> {
+    let mut params = content_type.split(';');
+    let media_type = params.next().unwrap_or("").trim();
+    if !media_type.eq_ignore_ascii_case("multipart/form-data") {
+        return Err(format!(
+            "Expected Content-Type multipart/form-data, got {}",
+            media_type
+        ));
+    }
+
+    params
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| unquote(value.trim()).to_string())
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| "Multipart Content-Type has no boundary".to_string())
+}
+
+/// Value of a `Content-Disposition` parameter such as `name="avatar"`
+fn disposition_param(disposition: &str, param: &str) -> Option<String> {
+    disposition
+        .split(';')
+        .skip(1)
+        .filter_map(|p| p.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case(param))
+        .map(|(_, value)| unquote(value.trim()).to_string())
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Fixed-window request counter keyed by client
+///
+/// State lives in the Lambda execution environment, so limits apply per warm
+/// container rather than globally across concurrent instances.
+#[derive(Debug)]
+pub struct RateLimiter {
+    max_requests: u32,
+    window: Duration,
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// Allow `max_requests` per `window` for each key
+    pub fn new(max_requests: u32, window: Duration) -> Self {
+        Self {
+            max_requests,
+            window,
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a request for `key`
+    ///
+    /// Returns `Err` with the time until the window resets when the limit has
+    /// already been reached.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Drop expired windows so the map stays bounded by active clients
+        windows.retain(|_, (start, _)| now.duration_since(*start) < self.window);
+
+        let (start, count) = windows.entry(key.to_string()).or_insert((now, 0));
+        if *count >= self.max_requests {
+            return Err(self.window.saturating_sub(now.duration_since(*start)));
+        }
+        *count += 1;
+        Ok(())
+    }
+}
@@ -1,6 +1,61 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cookie::CookieJar;
+use crate::error::ValidationIssue;
+use crate::multipart::MultipartFields;
+use crate::RouterError;
+
+/// Hop-by-hop headers that must not be forwarded by proxies (RFC 9110 section 7.6.1)
+pub const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Headers replaced by `[REDACTED]` in `Request::to_debug_json_redacted`
+pub const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "x-api-key",
+    "x-amz-security-token",
+];
+
+/// Header read by `Request::feature_flags`
+pub const FEATURE_FLAGS_HEADER: &str = "X-Feature-Flags";
+
+/// Whether `name` is hop-by-hop, either by definition or because `connection`
+/// (the value of the `Connection` header) lists it
+pub(crate) fn is_hop_by_hop(name: &str, connection: Option<&str>) -> bool {
+    HOP_BY_HOP_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(name))
+        || connection.is_some_and(|c| c.split(',').any(|t| t.trim().eq_ignore_ascii_case(name)))
+}
+
+/// Typed values attached to a `Context`, one per type
+///
+/// Values are shared between clones of the context.
+#[derive(Clone, Default)]
+struct Extensions(HashMap<TypeId, Arc<dyn Any + Send + Sync>>);
+
+impl std::fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Extensions({})", self.0.len())
+    }
+}
 
 /// Request context from Lambda event
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -10,6 +65,11 @@ pub struct Context {
     pub email: Option<String>,
     pub auth_token: Option<String>,
     pub custom: HashMap<String, Value>,
+    /// X-Ray trace id, the `Root=` segment of `X-Amzn-Trace-Id`
+    #[serde(default)]
+    pub trace_id: Option<String>,
+    #[serde(skip)]
+    extensions: Extensions,
 }
 
 impl Context {
@@ -20,6 +80,8 @@ impl Context {
             email: None,
             auth_token: None,
             custom: HashMap::new(),
+            trace_id: None,
+            extensions: Extensions::default(),
         }
     }
     
@@ -33,9 +95,35 @@ impl Context {
         self.custom.insert(key, value);
         self
     }
+    
+    /// Attach a typed value, e.g. verified claims set by auth middleware
+    ///
+    /// Replaces any value of the same type. Unlike `custom`, the value isn't
+    /// converted to JSON, and it isn't serialized with the context.
+    pub fn insert_extension<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.extensions.0.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+    
+    /// The value of type `T` attached with `insert_extension`
+    pub fn get_extension<T: Send + Sync + 'static>(&self) -> Option<&T> {
+        self.extensions
+            .0
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref::<T>())
+    }
+    
+    /// Detach the value of type `T`, returning whether there was one
+    pub fn remove_extension<T: Send + Sync + 'static>(&mut self) -> bool {
+        self.extensions.0.remove(&TypeId::of::<T>()).is_some()
+    }
 }
 
 /// HTTP Request representation
+///
+/// Cloning is a deep copy: headers, parameters, the decoded body, the cached
+/// parsed JSON body, and the raw Lambda event are all duplicated, so the cost
+/// grows with the payload size. Middleware that retries or mirrors requests
+/// pays it once per clone.
 #[derive(Debug, Clone)]
 pub struct Request {
     pub method: String,
@@ -45,23 +133,40 @@ pub struct Request {
     pub path_params: HashMap<String, String>,
     pub body: Option<String>,
     pub context: Context,
+    /// Pattern of the route being executed, set by the router before middleware runs
+    pub(crate) matched_route: Option<String>,
+    query_multi: HashMap<String, Vec<String>>,
+    body_bytes: Vec<u8>,
+    body_error: Option<String>,
+    json_cache: OnceLock<Option<Value>>,
     raw_event: Value,
 }
 
 impl Request {
     /// Create a new Request from Lambda event
+    ///
+    /// Accepts both API Gateway payload formats: 2.0 (HTTP API and Function
+    /// URLs), with `requestContext.http.method` and `rawPath`, and 1.0 (REST
+    /// API), with top-level `httpMethod` and `path`.
     pub fn from_lambda_event(event: Value) -> Self {
+        Self::from_lambda_event_with(event, &BodyConfig::default())
+    }
+    
+    /// Create a Request from a Lambda event, decoding the body as `config` says
+    pub fn from_lambda_event_with(event: Value, config: &BodyConfig) -> Self {
         let method = event["requestContext"]["http"]["method"]
             .as_str()
+            .or_else(|| event["httpMethod"].as_str())
             .unwrap_or("GET")
-            .to_string();
+            .to_uppercase();
         
         let path = event["rawPath"]
             .as_str()
+            .or_else(|| event["path"].as_str())
             .unwrap_or("/")
             .to_string();
         
-        let headers = event.get("headers")
+        let mut headers: HashMap<String, String> = event.get("headers")
             .and_then(|v| v.as_object())
             .map(|obj| {
                 obj.iter()
@@ -70,73 +175,607 @@ impl Request {
             })
             .unwrap_or_default();
         
-        let query_params = event.get("queryStringParameters")
+        // Payload v2 (HTTP API and Function URLs) moves cookies out of the headers
+        if let Some(cookies) = event.get("cookies").and_then(|v| v.as_array()) {
+            let has_cookie_header = headers.keys().any(|k| k.eq_ignore_ascii_case("cookie"));
+            if !has_cookie_header && !cookies.is_empty() {
+                let joined = cookies
+                    .iter()
+                    .filter_map(|c| c.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                headers.insert("cookie".to_string(), joined);
+            }
+        }
+        
+        let mut query_params: HashMap<String, String> = event.get("queryStringParameters")
             .and_then(|v| v.as_object())
             .map(|obj| {
                 obj.iter()
-                    .map(|(k, v)| (k.clone(), v.as_str().unwrap_or("").to_string()))
+                    .map(|(k, v)| {
+                        // `?flag=` may arrive as "", null or an empty array;
+                        // all of them mean present-but-empty
+                        let value = match v {
+                            Value::Array(values) => values.first().and_then(|v| v.as_str()),
+                            other => other.as_str(),
+                        };
+                        (k.clone(), value.unwrap_or("").to_string())
+                    })
                     .collect()
             })
             .unwrap_or_default();
         
-        let body = event.get("body")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
+        // REST API payloads list repeated keys in `multiValueQueryStringParameters`
+        let query_multi: HashMap<String, Vec<String>> = event
+            .get("multiValueQueryStringParameters")
+            .and_then(|v| v.as_object())
+            .map(|obj| {
+                obj.iter()
+                    .map(|(k, v)| {
+                        let values = v
+                            .as_array()
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|v| v.as_str())
+                            .map(|v| v.to_string())
+                            .collect();
+                        (k.clone(), values)
+                    })
+                    .filter(|(_, values): &(String, Vec<String>)| !values.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        // `query` returns the first value of a repeated key
+        for (name, values) in &query_multi {
+            query_params.insert(name.clone(), values[0].clone());
+        }
+        
+        let raw_body = event.get("body")
+            .and_then(|v| v.as_str());
+        let is_base64 = event["isBase64Encoded"].as_bool().unwrap_or(false);
+        let content_encoding = headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("content-encoding"))
+            .map(|(_, v)| v.as_str());
+        
+        let (body, body_bytes, body_error) =
+            match decode_body(raw_body, is_base64, content_encoding, config) {
+                Ok(Some(bytes)) => {
+                    let body = if config.lossy_utf8 {
+                        Some(String::from_utf8_lossy(&bytes).into_owned())
+                    } else {
+                        String::from_utf8(bytes.clone()).ok()
+                    };
+                    (body, bytes, None)
+                }
+                Ok(None) => (None, Vec::new(), None),
+                Err(e) => (None, Vec::new(), Some(e)),
+            };
+        
+        // Payload 1.0 carries the parameters API Gateway matched; the router
+        // replaces them with its own when it matches a route
+        let path_params = event.get("pathParameters")
+            .and_then(|v| v.as_object())
+            .map(|obj| {
+                obj.iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
         
         let request_id = event["requestContext"]["requestId"]
             .as_str()
             .unwrap_or("unknown")
             .to_string();
+        let mut context = Context::new(request_id);
+        context.trace_id = headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("x-amzn-trace-id"))
+            .and_then(|(_, v)| trace_root(v));
         
         Self {
             method,
             path,
             headers,
             query_params,
-            path_params: HashMap::new(),
+            query_multi,
+            path_params,
             body,
-            context: Context::new(request_id),
+            context,
+            matched_route: None,
+            body_bytes,
+            body_error,
+            json_cache: OnceLock::new(),
             raw_event: event,
         }
     }
     
     /// Get header value
+    ///
+    /// Names are matched case-insensitively, since REST API events keep the
+    /// client's casing while HTTP API events lower-case every name.
     pub fn header(&self, name: &str) -> Option<&String> {
         self.headers.get(name)
             .or_else(|| self.headers.get(&name.to_lowercase()))
+            .or_else(|| {
+                self.headers
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                    .map(|(_, v)| v)
+            })
+    }
+    
+    /// Iterate over every header name/value pair
+    ///
+    /// Headers present in the event's `multiValueHeaders` (REST API payloads)
+    /// yield one pair per value; all others yield their single value.
+    pub fn headers_iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        let multi = self.raw_event.get("multiValueHeaders").and_then(|v| v.as_object());
+        let mut pairs: Vec<(&str, &str)> = Vec::new();
+        
+        if let Some(multi) = multi {
+            for (name, values) in multi {
+                for value in values.as_array().into_iter().flatten() {
+                    if let Some(value) = value.as_str() {
+                        pairs.push((name.as_str(), value));
+                    }
+                }
+            }
+        }
+        
+        for (name, value) in &self.headers {
+            let expanded = multi.is_some_and(|m| m.keys().any(|k| k.eq_ignore_ascii_case(name)));
+            if !expanded {
+                pairs.push((name.as_str(), value.as_str()));
+            }
+        }
+        
+        pairs.into_iter()
+    }
+    
+    /// Headers safe to forward to a downstream service
+    ///
+    /// Drops the hop-by-hop headers in `HOP_BY_HOP_HEADERS` plus any header
+    /// named by the request's `Connection` header.
+    pub fn forwardable_headers(&self) -> Vec<(&str, &str)> {
+        let connection = self.header("Connection").map(|s| s.as_str());
+        self.headers_iter()
+            .filter(|(name, _)| !is_hop_by_hop(name, connection))
+            .collect()
+    }
+    
+    /// Get header value parsed into `T`
+    ///
+    /// Returns `None` when the header is absent, and a `BadRequest` error
+    /// naming the header when its value fails to parse.
+    pub fn header_as<T>(&self, name: &str) -> Option<Result<T, RouterError>>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.header(name).map(|value| {
+            value.trim().parse::<T>().map_err(|e| {
+                RouterError::BadRequest(format!("Invalid value for header '{}': {}", name, e))
+            })
+        })
+    }
+    
+    /// Get the `Referer` header
+    ///
+    /// Also accepts the correctly spelled `Referrer` sent by some clients.
+    pub fn referer(&self) -> Option<&str> {
+        self.header("Referer")
+            .or_else(|| self.header("Referrer"))
+            .map(|s| s.as_str())
+    }
+    
+    /// Get the `Origin` header
+    pub fn origin(&self) -> Option<&str> {
+        self.header("Origin").map(|s| s.as_str())
+    }
+    
+    /// Get the token from an `Authorization: Bearer <token>` header
+    pub fn bearer_token(&self) -> Option<&str> {
+        let value = self.header("Authorization")?.trim();
+        let (scheme, token) = value.split_once(' ')?;
+        (scheme.eq_ignore_ascii_case("bearer") && !token.trim().is_empty()).then(|| token.trim())
+    }
+    
+    /// Feature flags sent in the `X-Feature-Flags` header, e.g. `newUI,betaSearch`
+    ///
+    /// Flag names are case-sensitive; surrounding whitespace and empty items
+    /// are ignored. Empty when the header is absent.
+    pub fn feature_flags(&self) -> HashSet<String> {
+        self.feature_flags_from(FEATURE_FLAGS_HEADER)
+    }
+    
+    /// Feature flags from a comma-separated list in a custom header
+    pub fn feature_flags_from(&self, header: &str) -> HashSet<String> {
+        self.header(header)
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|flag| flag.trim())
+                    .filter(|flag| !flag.is_empty())
+                    .map(|flag| flag.to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    
+    /// Whether the `X-Feature-Flags` header lists `name`
+    pub fn has_flag(&self, name: &str) -> bool {
+        self.feature_flags().contains(name)
+    }
+    
+    /// Decode the claims of the Bearer JWT **without verifying its signature**
+    ///
+    /// Only use this when the token has already been verified upstream, e.g. by
+    /// an API Gateway authorizer. The claims are otherwise attacker-controlled.
+    pub fn jwt_claims_unverified(&self) -> Option<Value> {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+        
+        let payload = self.bearer_token()?.split('.').nth(1)?;
+        let bytes = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('=')).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+    
+    /// Cookies sent with the request
+    ///
+    /// Empty when there is no `Cookie` header.
+    pub fn cookie_jar(&self) -> CookieJar {
+        self.header("Cookie")
+            .map(|header| CookieJar::parse(header))
+            .unwrap_or_default()
+    }
+    
+    /// Value of a cookie set with `Response::signed_cookie`
+    ///
+    /// Returns `None` when the cookie is missing, unsigned, or its
+    /// signature doesn't match `secret`.
+    pub fn verify_signed_cookie(&self, name: &str, secret: &[u8]) -> Option<String> {
+        let jar = self.cookie_jar();
+        let signed = jar.get(name)?;
+        crate::cookie::verify(name, signed, secret).map(|value| value.to_string())
     }
     
     /// Get query parameter
+    ///
+    /// Returns `Some("")` for a parameter present without a value (`?flag=`)
+    /// and `None` when it is absent.
+    /// For a repeated key this is the first value; see `query_all`.
     pub fn query(&self, name: &str) -> Option<&String> {
         self.query_params.get(name)
     }
     
+    /// Deserialize the query parameters into a struct
+    ///
+    /// Values are parsed as form fields, so numbers, booleans and unit enum
+    /// variants work as they do in `serde_urlencoded`. Unknown parameters are
+    /// ignored unless `T` denies them; missing or unparseable fields are a
+    /// `BadRequest`.
+    pub fn query_parse<T: DeserializeOwned>(&self) -> Result<T, RouterError> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            RouterError::BadRequest(format!("Invalid query parameters: {}", e))
+        };
+        let encoded = serde_urlencoded::to_string(&self.query_params).map_err(|e| invalid(&e))?;
+        serde_urlencoded::from_str(&encoded).map_err(|e| invalid(&e))
+    }
+    
+    /// Every value of a query parameter, in request order
+    ///
+    /// Repeated keys such as `?tag=a&tag=b` come from the event's
+    /// `multiValueQueryStringParameters`; other keys yield their single value.
+    /// Empty when the parameter is absent.
+    pub fn query_all(&self, name: &str) -> Vec<&String> {
+        match self.query_multi.get(name) {
+            Some(values) => values.iter().collect(),
+            None => self.query_params.get(name).into_iter().collect(),
+        }
+    }
+    
+    /// Whether a query parameter is present, with or without a value
+    pub fn query_present(&self, name: &str) -> bool {
+        self.query_params.contains_key(name)
+    }
+    
+    /// Pattern of the route handling the request, e.g. `/api/users/:userId`
+    ///
+    /// Set by the router before middleware and the handler run, so it can
+    /// label metrics and logs without the cardinality of concrete paths.
+    /// `None` for requests that didn't match a route.
+    pub fn matched_route(&self) -> Option<&str> {
+        self.matched_route.as_deref()
+    }
+    
     /// Get path parameter
     pub fn path_param(&self, name: &str) -> Option<&String> {
         self.path_params.get(name)
     }
     
+    /// Parse a path parameter, e.g. `req.path_param_parse::<u64>("userId")?`
+    ///
+    /// A missing parameter or one that doesn't parse is a `BadRequest`
+    /// naming the parameter.
+    pub fn path_param_parse<T>(&self, name: &str) -> Result<T, RouterError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let value = self.path_params.get(name).ok_or_else(|| {
+            RouterError::BadRequest(format!("Missing path parameter '{}'", name))
+        })?;
+        value.parse::<T>().map_err(|e| {
+            RouterError::BadRequest(format!(
+                "Invalid value '{}' for path parameter '{}': {}",
+                value, name, e
+            ))
+        })
+    }
+    
     /// Parse JSON body
+    ///
+    /// Errors in nested fields are prefixed with a JSON pointer to the field,
+    /// e.g. `/address/zip: invalid type: string "abc", expected u32`.
     pub fn json<T: for<'de> Deserialize<'de>>(&self) -> Result<T, serde_json::Error> {
-        match &self.body {
-            Some(body) => serde_json::from_str(body),
-            None => serde_json::from_str("{}"),
+        let body = self.body.as_deref().unwrap_or("{}");
+        let mut deserializer = serde_json::Deserializer::from_str(body);
+        let value = serde_path_to_error::deserialize(&mut deserializer).map_err(with_pointer)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+    
+    /// Parse an `application/x-www-form-urlencoded` body
+    ///
+    /// Parameters on the media type such as `; charset=UTF-8` are ignored. A
+    /// missing or different `Content-Type`, or a body that doesn't fit `T`, is
+    /// a `BadRequest`. An empty body parses as no fields.
+    pub fn form<T: DeserializeOwned>(&self) -> Result<T, RouterError> {
+        let media_type = self
+            .header("Content-Type")
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim());
+
+        match media_type {
+            Some(media_type) if media_type.eq_ignore_ascii_case("application/x-www-form-urlencoded") => {}
+            Some(media_type) => {
+                return Err(RouterError::BadRequest(format!(
+                    "Expected Content-Type application/x-www-form-urlencoded, got {}",
+                    media_type
+                )))
+            }
+            None => {
+                return Err(RouterError::BadRequest(
+                    "Missing Content-Type, expected application/x-www-form-urlencoded".to_string(),
+                ))
+            }
+        }
+
+        serde_urlencoded::from_str(self.raw_body())
+            .map_err(|e| RouterError::BadRequest(format!("Invalid form body: {}", e)))
+    }
+    
+    /// Parts of a `multipart/form-data` body, parsed one at a time
+    ///
+    /// Each field borrows its content from the body. A missing or
+    /// non-multipart `Content-Type` or a malformed body yields a
+    /// `BadRequest` and ends the iteration.
+    pub fn multipart_fields(&self) -> MultipartFields<'_> {
+        MultipartFields::new(
+            self.header("Content-Type").map(|s| s.as_str()),
+            &self.body_bytes,
+        )
+    }
+    
+    /// Body parsed as a JSON value, cached after the first call
+    ///
+    /// Returns `None` if there is no body or it isn't valid JSON. The cache is
+    /// carried over by `clone`, so clones don't parse the body again.
+    pub fn json_value(&self) -> Option<&Value> {
+        self.json_cache
+            .get_or_init(|| self.body.as_deref().and_then(|b| serde_json::from_str(b).ok()))
+            .as_ref()
+    }
+    
+    /// Validate the JSON body against a JSON Schema document
+    ///
+    /// A missing body is validated as `null`. Every violation is reported as a
+    /// `ValidationIssue`, which renders as a 422 listing each pointer and
+    /// message. A body that isn't JSON is a `BadRequest`, and a schema that
+    /// doesn't compile is an `InternalError`.
+    pub fn json_schema_validate(&self, schema: &Value) -> Result<(), RouterError> {
+        let validator = jsonschema::validator_for(schema)
+            .map_err(|e| RouterError::InternalError(format!("Invalid JSON Schema: {}", e)))?;
+        
+        let instance = match (self.json_value(), &self.body) {
+            (Some(value), _) => value,
+            (None, None) => &Value::Null,
+            (None, Some(_)) => return Err(RouterError::BadRequest("Invalid JSON body".to_string())),
+        };
+        
+        let issues: Vec<ValidationIssue> = validator
+            .iter_errors(instance)
+            .map(|e| ValidationIssue {
+                pointer: e.instance_path().to_string(),
+                message: e.to_string(),
+            })
+            .collect();
+        
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(RouterError::ValidationFailed(issues))
         }
     }
     
     /// Get raw body
+    ///
+    /// Returns `None` for binary bodies that aren't valid UTF-8; use
+    /// `body_bytes` for those.
     pub fn body(&self) -> Option<&str> {
         self.body.as_deref()
     }
     
+    /// Body as a string, empty when there is none
+    ///
+    /// Binary bodies that aren't valid UTF-8 also yield an empty string.
+    pub fn raw_body(&self) -> &str {
+        self.body.as_deref().unwrap_or("")
+    }
+    
+    /// Get the body bytes after base64 and `Content-Encoding` decoding
+    pub fn body_bytes(&self) -> &[u8] {
+        &self.body_bytes
+    }
+    
+    /// Error from decoding the body, e.g. invalid base64 or an oversized gzip payload
+    ///
+    /// The router answers requests with a body error with a 400.
+    pub fn body_error(&self) -> Option<&str> {
+        self.body_error.as_deref()
+    }
+    
+    /// Reconstruct the full URL of the request (scheme, host, path and query)
+    ///
+    /// The scheme comes from `X-Forwarded-Proto` (defaulting to `https`) and the
+    /// host from the `Host` header, falling back to `requestContext.domainName`.
+    pub fn full_url(&self) -> String {
+        let scheme = self
+            .header("X-Forwarded-Proto")
+            .map(|s| s.as_str())
+            .unwrap_or("https");
+
+        let host = self.host().unwrap_or("localhost");
+        format!("{}://{}{}", scheme, host, self.path_and_query())
+    }
+
+    /// Host the request was sent to, from `Host` or `requestContext.domainName`
+    pub(crate) fn host(&self) -> Option<&str> {
+        self.header("Host")
+            .map(|s| s.as_str())
+            .or_else(|| self.raw_event["requestContext"]["domainName"].as_str())
+    }
+
+    /// Path followed by the query string, if there is one
+    pub(crate) fn path_and_query(&self) -> String {
+        let query = match self.raw_event["rawQueryString"].as_str() {
+            Some(raw) => raw.to_string(),
+            None => {
+                let mut pairs: Vec<_> = self.query_params.iter().collect();
+                pairs.sort();
+                pairs
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect::<Vec<_>>()
+                    .join("&")
+            }
+        };
+
+        if query.is_empty() {
+            self.path.clone()
+        } else {
+            format!("{}?{}", self.path, query)
+        }
+    }
+    
+    /// Client IP address reported by API Gateway
+    ///
+    /// Reads `requestContext.http.sourceIp` (HTTP API) or
+    /// `requestContext.identity.sourceIp` (REST API).
+    pub fn source_ip(&self) -> Option<IpAddr> {
+        let context = &self.raw_event["requestContext"];
+        context["http"]["sourceIp"]
+            .as_str()
+            .or_else(|| context["identity"]["sourceIp"].as_str())
+            .and_then(|ip| ip.parse().ok())
+    }
+    
+    /// Time API Gateway received the request
+    ///
+    /// Reads `requestContext.timeEpoch` (HTTP API) or
+    /// `requestContext.requestTimeEpoch` (REST API), both in milliseconds.
+    pub fn received_at(&self) -> Option<SystemTime> {
+        let context = &self.raw_event["requestContext"];
+        context["timeEpoch"]
+            .as_u64()
+            .or_else(|| context["requestTimeEpoch"].as_u64())
+            .map(|millis| UNIX_EPOCH + Duration::from_millis(millis))
+    }
+    
+    /// Structured view of the parsed request, e.g. for a debug echo endpoint
+    ///
+    /// The body is included as JSON when it parses, otherwise as a string.
+    /// Headers are included verbatim; use `to_debug_json_redacted` before
+    /// returning or logging it anywhere untrusted.
+    pub fn to_debug_json(&self) -> Value {
+        let body = match (self.json_value(), &self.body) {
+            (Some(value), _) => value.clone(),
+            (None, Some(body)) => Value::String(body.clone()),
+            (None, None) => Value::Null,
+        };
+        serde_json::json!({
+            "method": self.method,
+            "path": self.path,
+            "headers": self.headers,
+            "query": self.query_params,
+            "pathParams": self.path_params,
+            "body": body,
+            "requestId": self.context.request_id,
+        })
+    }
+    
+    /// Like `to_debug_json`, with the values of `SENSITIVE_HEADERS` replaced
+    pub fn to_debug_json_redacted(&self) -> Value {
+        let mut debug = self.to_debug_json();
+        if let Some(headers) = debug["headers"].as_object_mut() {
+            for (name, value) in headers.iter_mut() {
+                if SENSITIVE_HEADERS.iter().any(|s| s.eq_ignore_ascii_case(name)) {
+                    *value = Value::String("[REDACTED]".to_string());
+                }
+            }
+        }
+        debug
+    }
+    
     /// Get raw Lambda event
     pub fn raw_event(&self) -> &Value {
         &self.raw_event
     }
     
+    /// Check if the event came from a Lambda Function URL rather than API Gateway
+    pub fn is_function_url(&self) -> bool {
+        self.raw_event["requestContext"]["domainName"]
+            .as_str()
+            .is_some_and(|domain| domain.contains(".lambda-url."))
+    }
+    
     /// Check if request is CORS preflight
+    ///
+    /// A preflight is an OPTIONS request carrying `Access-Control-Request-Method`;
+    /// a bare OPTIONS request is not.
     pub fn is_preflight(&self) -> bool {
-        self.method == "OPTIONS"
+        self.method == "OPTIONS" && self.header("Access-Control-Request-Method").is_some()
+    }
+    
+    /// Whether the client asks to upgrade to a WebSocket
+    ///
+    /// Requires `Upgrade: websocket` and a `Connection` header listing
+    /// `Upgrade`, both case-insensitive. Lambda behind an HTTP API can't hold
+    /// the connection open, so handlers typically reject these.
+    pub fn is_websocket_upgrade(&self) -> bool {
+        let upgrade = self
+            .header("Upgrade")
+            .is_some_and(|v| v.split(',').any(|p| p.trim().eq_ignore_ascii_case("websocket")));
+        let connection = self
+            .header("Connection")
+            .is_some_and(|v| v.split(',').any(|t| t.trim().eq_ignore_ascii_case("upgrade")));
+        upgrade && connection
+    }
+    
+    /// Check if request is a cross-origin request (has an `Origin` header)
+    pub fn is_cors_request(&self) -> bool {
+        self.header("Origin").is_some()
     }
     
     /// Set path parameters (used internally by router)
@@ -149,3 +788,146 @@ impl Request {
         self.context = context;
     }
 }
+
+/// Prefix a deserialization error with the JSON pointer of the offending field
+///
+/// Syntax errors and errors at the document root are returned unchanged.
+fn with_pointer(err: serde_path_to_error::Error<serde_json::Error>) -> serde_json::Error {
+    use serde_path_to_error::Segment;
+    
+    let mut pointer = String::new();
+    for segment in err.path() {
+        let token = match segment {
+            Segment::Seq { index } => index.to_string(),
+            Segment::Map { key } => key.replace('~', "~0").replace('/', "~1"),
+            Segment::Enum { variant } => variant.clone(),
+            Segment::Unknown => continue,
+        };
+        pointer.push('/');
+        pointer.push_str(&token);
+    }
+    
+    let inner = err.into_inner();
+    if pointer.is_empty() || !inner.is_data() {
+        return inner;
+    }
+    serde::de::Error::custom(format!("{}: {}", pointer, inner))
+}
+
+/// Extract the `Root=` segment from an `X-Amzn-Trace-Id` header
+///
+/// e.g. `Root=1-5759e988-bd862e3fe1be46a994272793;Parent=53995c3f42cd8ad8;Sampled=1`
+fn trace_root(header: &str) -> Option<String> {
+    header
+        .split(';')
+        .filter_map(|field| field.trim().split_once('='))
+        .find(|(key, _)| key.eq_ignore_ascii_case("root"))
+        .map(|(_, value)| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Default upper bound on the size of a decoded request body
+pub const MAX_DECOMPRESSED_BODY_SIZE: usize = 6 * 1024 * 1024;
+
+/// How request bodies are decoded, set on the router with `Router::body_config`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BodyConfig {
+    /// Largest body accepted after base64 decoding and decompression
+    pub max_size: usize,
+    /// Decode bodies marked `isBase64Encoded`; when off, `body` keeps the base64 text
+    pub base64: bool,
+    /// Decompress `Content-Encoding: gzip` / `deflate` bodies
+    pub decompress: bool,
+    /// Replace invalid UTF-8 sequences instead of leaving `body` unset
+    pub lossy_utf8: bool,
+}
+
+impl BodyConfig {
+    pub fn new() -> Self {
+        Self {
+            max_size: MAX_DECOMPRESSED_BODY_SIZE,
+            base64: true,
+            decompress: true,
+            lossy_utf8: false,
+        }
+    }
+    
+    pub fn max_size(mut self, bytes: usize) -> Self {
+        self.max_size = bytes;
+        self
+    }
+    
+    pub fn base64(mut self, enabled: bool) -> Self {
+        self.base64 = enabled;
+        self
+    }
+    
+    pub fn decompress(mut self, enabled: bool) -> Self {
+        self.decompress = enabled;
+        self
+    }
+    
+    pub fn lossy_utf8(mut self, enabled: bool) -> Self {
+        self.lossy_utf8 = enabled;
+        self
+    }
+}
+
+impl Default for BodyConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decode a Lambda event body into bytes
+///
+/// Base64 is decoded first when `isBase64Encoded` is set, then the body is
+/// decompressed according to `Content-Encoding` (gzip or deflate), each only
+/// if `config` enables it.
+fn decode_body(
+    raw: Option<&str>,
+    is_base64: bool,
+    content_encoding: Option<&str>,
+    config: &BodyConfig,
+) -> Result<Option<Vec<u8>>, String> {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use std::io::Read;
+
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
+
+    let bytes = if is_base64 && config.base64 {
+        STANDARD
+            .decode(raw.trim())
+            .map_err(|e| format!("Invalid base64 body: {}", e))?
+    } else {
+        raw.as_bytes().to_vec()
+    };
+
+    let encoding = content_encoding
+        .filter(|_| config.decompress)
+        .map(|e| e.trim().to_ascii_lowercase());
+    let mut decoder: Box<dyn Read> = match encoding.as_deref() {
+        Some("gzip") | Some("x-gzip") => Box::new(flate2::read::GzDecoder::new(&bytes[..])),
+        Some("deflate") => Box::new(flate2::read::ZlibDecoder::new(&bytes[..])),
+        _ if bytes.len() > config.max_size => {
+            return Err(format!("Body exceeds {} bytes", config.max_size))
+        }
+        _ => return Ok(Some(bytes)),
+    };
+
+    // Read one byte past the limit so oversized payloads are detected
+    let mut decoded = Vec::new();
+    (&mut decoder)
+        .take(config.max_size as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|e| format!("Invalid compressed body: {}", e))?;
+
+    if decoded.len() > config.max_size {
+        return Err(format!("Decompressed body exceeds {} bytes", config.max_size));
+    }
+
+    Ok(Some(decoded))
+}
@@ -1,6 +1,29 @@
+use bytes::Bytes;
+use futures::stream::{BoxStream, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::matcher::percent_encode;
+use crate::middleware::SkipReason;
+use crate::request::is_hop_by_hop;
+
+/// Default upper bound on a response body, matching Lambda's 6 MB payload limit
+pub const MAX_RESPONSE_BODY_SIZE: usize = 6 * 1024 * 1024;
+
+/// Body of a streaming response
+///
+/// Shared between clones so `Response` stays `Clone`; whichever clone reaches
+/// `Router::into_streaming_service` first consumes it.
+#[derive(Clone)]
+pub(crate) struct BodyStream(Arc<Mutex<Option<BoxStream<'static, Bytes>>>>);
+
+impl std::fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("BodyStream")
+    }
+}
 
 /// HTTP Response builder
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,6 +34,17 @@ pub struct Response {
     pub body: String,
     #[serde(rename = "isBase64Encoded")]
     pub is_base64_encoded: bool,
+    /// `Set-Cookie` values, sent in the v2 `cookies` array (see `to_json_v1` for 1.0)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cookies: Vec<String>,
+    /// Set by middleware that answered without running the handler
+    #[serde(skip)]
+    pub short_circuit: Option<SkipReason>,
+    /// Set by `without_cors` so the router and `CorsMiddleware` leave the response alone
+    #[serde(skip)]
+    pub(crate) cors_disabled: bool,
+    #[serde(skip)]
+    pub(crate) stream: Option<BodyStream>,
 }
 
 impl Response {
@@ -24,6 +58,10 @@ impl Response {
             headers,
             body: String::new(),
             is_base64_encoded: false,
+            cookies: Vec::new(),
+            short_circuit: None,
+            cors_disabled: false,
+            stream: None,
         }
     }
 
@@ -51,9 +89,64 @@ impl Response {
         self
     }
 
-    /// Add header
+    /// Set a header, replacing any existing value
+    ///
+    /// Header names are compared case-insensitively, so the last write wins.
+    /// In a middleware chain that means the outermost middleware (the one
+    /// registered first) has the final say, since it sees the response last.
     pub fn header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
-        self.headers.insert(key.into(), value.into());
+        let key = key.into();
+        self.headers.retain(|name, _| !name.eq_ignore_ascii_case(&key));
+        self.headers.insert(key, value.into());
+        self
+    }
+
+    /// Add a `Set-Cookie` value such as `theme=dark; Path=/`
+    pub fn cookie(mut self, set_cookie: impl Into<String>) -> Self {
+        self.cookies.push(set_cookie.into());
+        self
+    }
+
+    /// Set a cookie whose value is signed with HMAC-SHA256, for stateless sessions
+    ///
+    /// The value is sent as `value.signature` with `Path=/; HttpOnly; Secure;
+    /// SameSite=Lax`; read it back with `Request::verify_signed_cookie`. The
+    /// value is readable by the client, only tamper-proof, and must not
+    /// contain characters that need quoting in a cookie (`;`, `,`, spaces).
+    pub fn signed_cookie(self, name: &str, value: &str, secret: &[u8]) -> Self {
+        let signed = crate::cookie::sign(name, value, secret);
+        self.cookie(format!(
+            "{}={}; Path=/; HttpOnly; Secure; SameSite=Lax",
+            name, signed
+        ))
+    }
+
+    /// Forbid caching, for responses carrying tokens or payment details
+    ///
+    /// Sets `Cache-Control: no-store, no-cache, must-revalidate` and, for
+    /// HTTP/1.0 caches, `Pragma: no-cache`.
+    pub fn no_store(self) -> Self {
+        self.header("Cache-Control", "no-store, no-cache, must-revalidate")
+            .header("Pragma", "no-cache")
+    }
+
+    /// Add a value to a header, keeping any existing value
+    ///
+    /// Values are joined with `", "`, which is equivalent to sending the header
+    /// repeatedly for list-valued headers such as `Cache-Control` or `Vary`.
+    /// The existing header's name casing is kept.
+    pub fn append_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        let key = key.into();
+        let value = value.into();
+        match self.headers.iter_mut().find(|(name, _)| name.eq_ignore_ascii_case(&key)) {
+            Some((_, existing)) => {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            }
+            None => {
+                self.headers.insert(key, value);
+            }
+        }
         self
     }
 
@@ -63,7 +156,23 @@ impl Response {
         self
     }
 
-    /// Add CORS headers
+    /// Remove hop-by-hop headers, e.g. from a response relayed by a proxy handler
+    pub fn without_hop_by_hop(mut self) -> Self {
+        let connection = self
+            .headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("connection"))
+            .map(|(_, v)| v.clone());
+        self.headers
+            .retain(|name, _| !is_hop_by_hop(name, connection.as_deref()));
+        self
+    }
+
+    /// Add wildcard CORS headers
+    ///
+    /// Constructors don't add CORS headers; the router applies its
+    /// `CorsConfig` to every response instead. Use this only for responses
+    /// built outside a router.
     pub fn with_cors(mut self) -> Self {
         self.headers
             .insert("Access-Control-Allow-Origin".to_string(), "*".to_string());
@@ -80,14 +189,167 @@ impl Response {
         self
     }
 
+    /// Remove CORS headers and keep the router from adding any
+    ///
+    /// For responses to non-browser clients, e.g. service-to-service calls.
+    pub fn without_cors(mut self) -> Self {
+        self.headers.retain(|name, _| {
+            !name.to_ascii_lowercase().starts_with("access-control-")
+        });
+        self.cors_disabled = true;
+        self
+    }
+
+    /// Replace the status code, keeping body and headers
+    ///
+    /// The `status` member of an error body (see `Response::error`) is
+    /// updated to match.
+    pub fn with_status(mut self, status_code: u16) -> Self {
+        self.status_code = status_code;
+        let has_status = serde_json::from_str::<Value>(&self.body)
+            .is_ok_and(|body| body["error"].get("status").is_some());
+        if has_status {
+            self = self.with_error_field("status", json!(status_code));
+        }
+        self
+    }
+
+    /// Mark the response as a middleware short-circuit, for outer middleware to report
+    pub fn short_circuit(mut self, reason: SkipReason) -> Self {
+        self.short_circuit = Some(reason);
+        self
+    }
+
+    /// Why a middleware short-circuited the chain, if it did
+    pub fn short_circuit_reason(&self) -> Option<&SkipReason> {
+        self.short_circuit.as_ref()
+    }
+
+    /// 200 response whose body is streamed, for payloads over Lambda's 6 MB limit
+    ///
+    /// Only `Router::into_streaming_service` sends the stream; `to_json`
+    /// produces just the metadata prelude for it. The router's body
+    /// post-processing (serializer, binary encoding, size limit) is skipped.
+    pub fn stream(body: impl Stream<Item = Bytes> + Send + 'static) -> Self {
+        let mut response = Self::new(200);
+        response.stream = Some(BodyStream(Arc::new(Mutex::new(Some(body.boxed())))));
+        response
+    }
+
+    /// Whether the body is streamed, see `stream`
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Take the body stream, leaving `None` behind for other clones
+    pub(crate) fn take_stream(&self) -> Option<BoxStream<'static, Bytes>> {
+        let stream = self.stream.as_ref()?;
+        stream.0.lock().unwrap().take()
+    }
+
+    /// Whether the status is 2xx
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status_code)
+    }
+
+    /// Whether the status is 3xx
+    pub fn is_redirect(&self) -> bool {
+        (300..400).contains(&self.status_code)
+    }
+
+    /// Whether the status is 4xx
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.status_code)
+    }
+
+    /// Whether the status is 5xx
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.status_code)
+    }
+
+    /// Add a member to the `error` object of an error body
+    ///
+    /// Bodies without the error envelope (see `Response::error`) are left
+    /// unchanged.
+    pub fn with_error_field(mut self, key: &str, value: Value) -> Self {
+        if let Ok(mut body) = serde_json::from_str::<Value>(&self.body) {
+            if let Some(error) = body.get_mut("error").and_then(Value::as_object_mut) {
+                error.insert(key.to_string(), value);
+                self.body = body.to_string();
+            }
+        }
+        self
+    }
+
+    /// Add a top-level member to a JSON object body
+    ///
+    /// Intended for RFC 7807 extension members on `Response::problem`, but works
+    /// for any JSON object body. Non-object bodies are left unchanged.
+    pub fn with_extension(mut self, key: &str, value: Value) -> Self {
+        if let Ok(Value::Object(mut map)) = serde_json::from_str::<Value>(&self.body) {
+            map.insert(key.to_string(), value);
+            self.body = Value::Object(map).to_string();
+        }
+        self
+    }
+
+    /// Rewrite a relative `Location` header into an absolute URL
+    ///
+    /// `base_url` is the full URL of the originating request (see
+    /// `Request::full_url`). Absolute locations are left untouched.
+    pub fn absolute_location(mut self, base_url: &str) -> Self {
+        if let Some((_, location)) = self
+            .headers
+            .iter_mut()
+            .find(|(k, _)| k.eq_ignore_ascii_case("location"))
+        {
+            *location = resolve_location(base_url, location);
+        }
+        self
+    }
+
     /// Convert to JSON value
+    ///
+    /// The body size isn't checked here; the router applies
+    /// `Router::max_response_size` before serializing.
     pub fn to_json(&self) -> Value {
-        json!({
+        // Streaming responses are sent as a metadata prelude followed by the body
+        if self.is_streaming() {
+            return json!({
+                "statusCode": self.status_code,
+                "headers": self.headers,
+                "cookies": self.cookies
+            });
+        }
+
+        let mut value = json!({
+            "statusCode": self.status_code,
+            "headers": self.headers,
+            "body": self.body,
+            "isBase64Encoded": self.is_base64_encoded
+        });
+        if !self.cookies.is_empty() {
+            value["cookies"] = json!(self.cookies);
+        }
+        value
+    }
+
+    /// Convert to a payload format 1.0 response, for REST APIs and ALB
+    ///
+    /// Format 1.0 has no `cookies` array, so cookies are sent as
+    /// `multiValueHeaders` `Set-Cookie` entries. ALB only forwards them when
+    /// multi-value headers are enabled on the target group.
+    pub fn to_json_v1(&self) -> Value {
+        let mut value = json!({
             "statusCode": self.status_code,
             "headers": self.headers,
             "body": self.body,
             "isBase64Encoded": self.is_base64_encoded
-        })
+        });
+        if !self.cookies.is_empty() {
+            value["multiValueHeaders"] = json!({ "Set-Cookie": self.cookies });
+        }
+        value
     }
 
     /// Create Response from JSON value (for controller compatibility)
@@ -117,84 +379,266 @@ impl Response {
             headers,
             body,
             is_base64_encoded: value["isBase64Encoded"].as_bool().unwrap_or(false),
+            cookies: value["cookies"]
+                .as_array()
+                .map(|cookies| {
+                    cookies
+                        .iter()
+                        .filter_map(|c| c.as_str().map(|c| c.to_string()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            short_circuit: None,
+            cors_disabled: false,
+            stream: None,
         }
     }
 
     // Convenience constructors
 
+    /// JSON response with any status, e.g. 202 Accepted or 409 Conflict
+    ///
+    /// The named constructors such as `ok` and `created` are shorthands for it.
+    pub fn json_with_status(status: u16, body: impl Serialize) -> Self {
+        Self::new(status).json_body(&body)
+    }
+
     /// 200 OK response
     pub fn ok(body: Value) -> Self {
-        Self::new(200).json(body).with_cors()
+        Self::json_with_status(200, body)
+    }
+
+    /// 200 OK response with a string body, picking the Content-Type from its contents
+    ///
+    /// A body starting with `{` or `[` is sent as JSON, one starting with `<`
+    /// as HTML, and anything else as plain text. Leading whitespace is
+    /// ignored. Use `ok`, `text` or `header` when the type is known, and
+    /// `with_status` for other statuses.
+    pub fn auto(body: impl Into<String>) -> Self {
+        let body = body.into();
+        let content_type = match body.trim_start().chars().next() {
+            Some('{') | Some('[') => "application/json",
+            Some('<') => "text/html",
+            _ => "text/plain",
+        };
+        let mut response = Self::new(200).header("Content-Type", content_type);
+        response.body = body;
+        response
+    }
+
+    /// 200 OK response with extra headers, e.g. pagination or rate-limit info
+    ///
+    /// Headers are applied in order with `header`, so a later duplicate wins.
+    pub fn ok_with_headers(body: Value, headers: &[(&str, &str)]) -> Self {
+        headers
+            .iter()
+            .fold(Self::ok(body), |response, (name, value)| response.header(*name, *value))
+    }
+
+    /// 200 OK response for cursor-based pagination
+    ///
+    /// Body shape: `{"items": [...], "nextCursor": "..." | null}`. Build the
+    /// cursor with `cursor::encode`.
+    pub fn paginated_cursor<T: Serialize>(items: &[T], next_cursor: Option<&str>) -> Self {
+        Self::ok(json!({
+            "items": items,
+            "nextCursor": next_cursor
+        }))
     }
 
     /// 201 Created response
     pub fn created(body: Value) -> Self {
-        Self::new(201).json(body).with_cors()
+        Self::json_with_status(201, body)
+    }
+
+    /// 200 OK response with an empty JSON object body
+    pub fn ok_empty() -> Self {
+        Self::ok(json!({}))
+    }
+
+    /// 202 Accepted response with an empty JSON object body
+    pub fn accepted_empty() -> Self {
+        Self::json_with_status(202, json!({}))
     }
 
     /// 204 No Content response
     pub fn no_content() -> Self {
-        Self::new(204).with_cors()
+        Self::new(204)
+    }
+
+    /// 301 Moved Permanently redirect
+    ///
+    /// For resources that have moved for good; clients and caches may
+    /// remember it, so don't use it for per-request decisions.
+    pub fn moved_permanently(location: &str) -> Self {
+        Self::new(301).text("").header("Location", location)
+    }
+
+    /// 302 Found redirect
+    ///
+    /// A temporary redirect, e.g. sending a signed-out `GET` to `/login`.
+    pub fn redirect(location: &str) -> Self {
+        Self::new(302).text("").header("Location", location)
+    }
+
+    /// 303 See Other redirect
+    ///
+    /// Use after handling a `POST` (a form submission, an OAuth callback) so
+    /// the client follows up with a `GET` instead of resubmitting.
+    pub fn see_other(location: &str) -> Self {
+        Self::new(303).text("").header("Location", location)
+    }
+
+    /// 302 Found redirect with URL-encoded query parameters appended
+    ///
+    /// Parameters are joined onto any query string already present in
+    /// `location`, ahead of a `#fragment` if there is one.
+    pub fn redirect_with_query(location: &str, params: &[(&str, &str)]) -> Self {
+        let (base, fragment) = match location.split_once('#') {
+            Some((base, fragment)) => (base, Some(fragment)),
+            None => (location, None),
+        };
+
+        let query = params
+            .iter()
+            .map(|(k, v)| format!("{}={}", percent_encode(k), percent_encode(v)))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut target = base.to_string();
+        if !query.is_empty() {
+            if !target.contains('?') {
+                target.push('?');
+            } else if !target.ends_with('?') && !target.ends_with('&') {
+                target.push('&');
+            }
+            target.push_str(&query);
+        }
+        if let Some(fragment) = fragment {
+            target.push('#');
+            target.push_str(fragment);
+        }
+
+        Self::redirect(&target)
+    }
+
+    /// JSON error response with the standard error body
+    ///
+    /// The body is `{"error": {"message": ..., "status": ...}}`. All error
+    /// constructors use it, and the router adds the `requestId` of the
+    /// request to error responses it sends. Use `with_error_field` for
+    /// further members.
+    pub fn error(status: u16, message: &str) -> Self {
+        let body = json!({
+            "error": {
+                "message": message,
+                "status": status
+            }
+        });
+        Self::json_with_status(status, body)
     }
 
     /// 400 Bad Request response
     pub fn bad_request(message: &str) -> Self {
-        Self::new(400)
-            .json(json!({
-                "error": "Bad Request",
-                "message": message
-            }))
-            .with_cors()
+        Self::error(400, message)
     }
 
     /// 401 Unauthorized response
     pub fn unauthorized(message: &str) -> Self {
-        Self::new(401)
-            .json(json!({
-                "error": "Unauthorized",
-                "message": message
-            }))
-            .with_cors()
+        Self::error(401, message)
     }
 
     /// 403 Forbidden response
     pub fn forbidden(message: &str) -> Self {
-        Self::new(403)
-            .json(json!({
-                "error": "Forbidden",
-                "message": message
-            }))
-            .with_cors()
+        Self::error(403, message)
     }
 
     /// 404 Not Found response
     pub fn not_found(message: &str) -> Self {
-        Self::new(404)
-            .json(json!({
-                "error": "Not Found",
-                "message": message
-            }))
-            .with_cors()
+        Self::error(404, message)
     }
 
-    /// 405 Method Not Allowed response
-    pub fn method_not_allowed(message: &str) -> Self {
-        Self::new(405)
-            .json(json!({
-                "error": "Method Not Allowed",
-                "message": message
-            }))
-            .with_cors()
+    /// 405 Method Not Allowed response with an `Allow` header listing `allowed`
+    pub fn method_not_allowed(allowed: &[&str]) -> Self {
+        let response = Self::error(405, &format!("Allowed methods: {}", allowed.join(", ")))
+            .with_error_field("allowedMethods", json!(allowed));
+        // An empty `Allow` would claim no method is allowed, so leave it out
+        if allowed.is_empty() {
+            response
+        } else {
+            response.header("Allow", allowed.join(", "))
+        }
+    }
+
+    /// 409 Conflict response, e.g. for a failed optimistic-locking check
+    pub fn conflict(message: &str) -> Self {
+        Self::error(409, message)
+    }
+
+    /// 415 Unsupported Media Type response
+    pub fn unsupported_media_type(message: &str) -> Self {
+        Self::error(415, message)
+    }
+
+    /// 422 Unprocessable Entity response, for well-formed requests that fail validation
+    pub fn unprocessable_entity(message: &str) -> Self {
+        Self::error(422, message)
+    }
+
+    /// 429 Too Many Requests response
+    ///
+    /// Add a `Retry-After` header with `retry_after`.
+    pub fn too_many_requests(message: &str) -> Self {
+        Self::error(429, message)
+    }
+
+    /// Set `Retry-After` to a delay in seconds
+    pub fn retry_after(self, seconds: u64) -> Self {
+        self.header("Retry-After", seconds.to_string())
     }
 
     /// 500 Internal Server Error response
     pub fn internal_error(message: &str) -> Self {
-        Self::new(500)
+        Self::error(500, message)
+    }
+
+    /// RFC 7807 problem details response (`application/problem+json`)
+    ///
+    /// The body carries `type` (`about:blank`), `title`, `status` and `detail`.
+    /// Use `with_extension` to add extension members or a specific `type`.
+    pub fn problem(status: u16, title: &str, detail: &str) -> Self {
+        Self::new(status)
             .json(json!({
-                "error": "Internal Server Error",
-                "message": message
+                "type": "about:blank",
+                "title": title,
+                "status": status,
+                "detail": detail
             }))
-            .with_cors()
+            .header("Content-Type", "application/problem+json")
+    }
+
+    /// 504 Gateway Timeout response
+    pub fn gateway_timeout(message: &str) -> Self {
+        Self::error(504, message)
+    }
+
+    /// Error response with the error's message in the standard error body
+    ///
+    /// The message is sent to the client verbatim; use `from_error_safe` for
+    /// errors that may contain internal details.
+    pub fn from_error<E: std::fmt::Display>(status: u16, err: E) -> Self {
+        Self::error(status, &err.to_string())
+    }
+
+    /// Error response with a generic message; the real error is only logged
+    pub fn from_error_safe<E: std::fmt::Display>(status: u16, err: E) -> Self {
+        tracing::error!(
+            status,
+            reason = status_reason(status),
+            error = %err,
+            "returning a generic error response"
+        );
+        Self::error(status, "An unexpected error occurred")
     }
 
     /// CORS preflight response
@@ -202,3 +646,55 @@ impl Response {
         Self::new(200).text("").with_cors()
     }
 }
+
+/// Reason phrase for a status code
+pub(crate) fn status_reason(status: u16) -> &'static str {
+    match status {
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Entity",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        504 => "Gateway Timeout",
+        _ if (400..500).contains(&status) => "Client Error",
+        _ if (500..600).contains(&status) => "Server Error",
+        _ => "Error",
+    }
+}
+
+/// Resolve `location` against `base_url` the way a browser would
+fn resolve_location(base_url: &str, location: &str) -> String {
+    if location.starts_with("http://") || location.starts_with("https://") {
+        return location.to_string();
+    }
+
+    let (scheme, rest) = base_url.split_once("://").unwrap_or(("https", base_url));
+
+    // Protocol-relative: //host/path
+    if let Some(stripped) = location.strip_prefix("//") {
+        return format!("{}://{}", scheme, stripped);
+    }
+
+    let authority = rest.split(['/', '?']).next().unwrap_or("");
+    let origin = format!("{}://{}", scheme, authority);
+
+    if location.starts_with('/') {
+        return format!("{}{}", origin, location);
+    }
+
+    // Relative to the directory of the current path
+    let path = rest[authority.len()..].split('?').next().unwrap_or("");
+    let dir = match path.rfind('/') {
+        Some(i) => &path[..=i],
+        None => "/",
+    };
+    format!("{}{}{}", origin, dir, location)
+}
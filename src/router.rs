@@ -1,16 +1,74 @@
 use async_trait::async_trait;
+use bytes::Bytes;
 use futures::future::BoxFuture;
-use lambda_runtime::{Error, LambdaEvent};
-use serde_json::Value;
-use std::sync::Arc;
+use futures::stream::{BoxStream, StreamExt};
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use http::StatusCode;
+use lambda_runtime::{Error, LambdaEvent, MetadataPrelude, StreamResponse};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::middleware::CorsMiddleware;
+use crate::cors::CorsConfig;
+use crate::error::IntoResponse;
+use crate::ip::IpCidr;
+use crate::matcher::{percent_decode, warm_up_statics};
+use crate::metrics::{LatencyHistogram, Percentiles};
+use crate::middleware::{MiddlewareFn, Next, SkipReason};
+use crate::rate_limit::RateLimiter;
+use crate::request::BodyConfig;
+use crate::response::MAX_RESPONSE_BODY_SIZE;
 use crate::{Context, Middleware, PathMatcher, Request, Response, Result, RouterError};
 
 /// Handler function type
+///
+/// A shared, type-erased handler taking the request and its context and
+/// returning a boxed future. Handlers built at runtime as
+/// `Box<dyn Fn(Request, Context) -> BoxFuture<...>>` convert with `Arc::from`
+/// and can be registered with `Router::add`.
 pub type HandlerFn =
     Arc<dyn Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync>;
 
+/// Function used to serialize JSON response bodies
+pub type BodySerializer = Box<dyn Fn(&Value) -> String + Send + Sync>;
+
+/// Hook invoked when a request fails
+pub type ErrorHook = Box<dyn Fn(&Request, &RouterError) + Send + Sync>;
+
+/// Answers a request before routing, or lets it through with `None`
+pub type Interceptor = Box<dyn Fn(&Request) -> Option<Response> + Send + Sync>;
+
+/// Body stream sent by `Router::into_streaming_service`
+pub type ResponseStream = BoxStream<'static, std::result::Result<Bytes, Error>>;
+
+/// Future returned by the service from `Router::into_streaming_service`
+pub type StreamingResponseFuture =
+    BoxFuture<'static, std::result::Result<StreamResponse<ResponseStream>, Error>>;
+
+/// Decides whether a request may reach the matched route's handler
+pub type Authorizer = Arc<dyn Fn(&Context, &RouteInfo) -> bool + Send + Sync>;
+
+/// The route a request was matched to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteInfo {
+    /// Registered method, upper-cased
+    pub method: String,
+    /// Route pattern as registered, e.g. `/api/users/:userId`
+    pub pattern: String,
+}
+
+/// Outcome of `Router::match_route`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteMatch {
+    /// The matched route
+    pub route: RouteInfo,
+    /// Path parameters captured from the path
+    pub params: HashMap<String, String>,
+}
+
 /// Handler trait for route handlers
 #[async_trait]
 pub trait Handler: Send + Sync {
@@ -22,13 +80,205 @@ struct Route {
     method: String,
     matcher: PathMatcher,
     handler: HandlerFn,
+    consumes: Vec<String>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    /// Middleware of the enclosing groups, run before the route's own
+    group_middlewares: Vec<Arc<dyn Middleware>>,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    no_store: bool,
+    priority: i32,
+}
+
+impl Route {
+    /// Whether the route accepts a request with this `Content-Type`
+    fn accepts(&self, content_type: Option<&str>) -> bool {
+        if self.consumes.is_empty() {
+            return true;
+        }
+        let Some(content_type) = content_type else {
+            return false;
+        };
+        let media_type = content_type.split(';').next().unwrap_or("").trim();
+        self.consumes
+            .iter()
+            .any(|accepted| media_type_matches(accepted, media_type))
+    }
+}
+
+/// Handle returned by route registration for per-route options
+pub struct RouteHandle<'a> {
+    route: &'a mut Route,
+}
+
+impl RouteHandle<'_> {
+    /// Only select this route for requests with a matching `Content-Type`
+    ///
+    /// May be called several times to accept multiple media types; `type/*`
+    /// wildcards are supported. When routes share a method and path, the first
+    /// one that accepts the request's content type handles it, and a 415 is
+    /// returned if none do.
+    pub fn consumes(self, media_type: &str) -> Self {
+        self.route.consumes.push(media_type.to_ascii_lowercase());
+        self
+    }
+
+    /// Limit this route to `max_requests` per `window` for each client IP
+    ///
+    /// Overrides the router-wide limit set with `Router::rate_limit`.
+    pub fn rate_limit(self, max_requests: u32, window: Duration) -> Self {
+        self.route.rate_limiter = Some(Arc::new(RateLimiter::new(max_requests, window)));
+        self
+    }
+
+    /// Run a middleware for this route only
+    ///
+    /// Route middleware runs after global and group middleware, just before
+    /// the handler. Call repeatedly to attach several; they run in the order
+    /// attached.
+    pub fn with_middleware(self, middleware: impl Middleware + 'static) -> Self {
+        self.route.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Try this route before (higher) or after (lower) other matching routes
+    ///
+    /// Routes default to priority 0. Among routes matching a request, the
+    /// highest priority wins, and specificity only decides between routes of
+    /// equal priority.
+    pub fn priority(self, priority: i32) -> Self {
+        self.route.priority = priority;
+        self
+    }
+
+    /// Mark this route's responses as not cacheable, see `Response::no_store`
+    ///
+    /// Applies to the responses the handler chain returns, not to errors.
+    pub fn no_store(self) -> Self {
+        self.route.no_store = true;
+        self
+    }
+}
+
+/// Routes sharing a path prefix and middleware, created with `Router::group`
+///
+/// Routes are added to the parent router as they are registered, with the
+/// prefix prepended. Group middleware runs after the router's global
+/// middleware and before the handler, and only for routes in the group. It
+/// applies to every route of the group, including ones registered before
+/// the middleware was added.
+pub struct RouteGroup<'a> {
+    router: &'a mut Router,
+    prefix: String,
+    middlewares: Vec<Arc<dyn Middleware>>,
+    routes: Vec<usize>,
+}
+
+impl RouteGroup<'_> {
+    /// Add a middleware that runs only for routes in this group
+    pub fn use_middleware(&mut self, middleware: impl Middleware + 'static) {
+        let middleware: Arc<dyn Middleware> = Arc::new(middleware);
+        for &index in &self.routes {
+            self.router.routes[index]
+                .group_middlewares
+                .push(middleware.clone());
+        }
+        self.middlewares.push(middleware);
+    }
+
+    /// Add a GET route under the group prefix
+    pub fn get<F>(&mut self, path: &str, handler: F) -> RouteHandle<'_>
+    where
+        F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
+    {
+        self.add("GET", path, Arc::new(handler))
+    }
+
+    /// Add a POST route under the group prefix
+    pub fn post<F>(&mut self, path: &str, handler: F) -> RouteHandle<'_>
+    where
+        F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
+    {
+        self.add("POST", path, Arc::new(handler))
+    }
+
+    /// Add a PUT route under the group prefix
+    pub fn put<F>(&mut self, path: &str, handler: F) -> RouteHandle<'_>
+    where
+        F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
+    {
+        self.add("PUT", path, Arc::new(handler))
+    }
+
+    /// Add a DELETE route under the group prefix
+    pub fn delete<F>(&mut self, path: &str, handler: F) -> RouteHandle<'_>
+    where
+        F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
+    {
+        self.add("DELETE", path, Arc::new(handler))
+    }
+
+    /// Add a PATCH route under the group prefix
+    pub fn patch<F>(&mut self, path: &str, handler: F) -> RouteHandle<'_>
+    where
+        F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
+    {
+        self.add("PATCH", path, Arc::new(handler))
+    }
+
+    /// Add a route for any method under the group prefix
+    pub fn add(&mut self, method: &str, path: &str, handler: HandlerFn) -> RouteHandle<'_> {
+        let full_path = join_prefix(&self.prefix, path);
+        let index = self.router.routes.len();
+        self.router.add(method, &full_path, handler);
+        self.routes.push(index);
+
+        let route = &mut self.router.routes[index];
+        route
+            .group_middlewares
+            .extend(self.middlewares.iter().cloned());
+        RouteHandle { route }
+    }
 }
 
 /// Router for handling Lambda HTTP requests
+///
+/// When several routes match a path, the most specific one handles it.
+/// Patterns are compared segment by segment from the left, and at the first
+/// segment where they differ a static segment beats a parameter, which beats
+/// a wildcard. So `/api/users/me` wins over `/api/users/:userId`, which wins
+/// over `/api/*rest`, whatever the registration order. A pattern that ends
+/// beats one continuing with a wildcard, so `/docs` wins over `/docs/*path`
+/// for `/docs`. Equally specific routes are tried in registration order.
 pub struct Router {
     routes: Vec<Route>,
     middlewares: Vec<Arc<dyn Middleware>>,
+    cors: Option<CorsConfig>,
     not_found_handler: Option<HandlerFn>,
+    method_fallbacks: HashMap<String, HandlerFn>,
+    absolute_redirects: bool,
+    serializer: Option<BodySerializer>,
+    suggest_routes: bool,
+    ip_allowlist: Vec<IpCidr>,
+    ip_denylist: Vec<IpCidr>,
+    canonical_host: Option<(String, bool)>,
+    health_check_paths: Vec<String>,
+    spa_fallback: Option<String>,
+    spa_api_prefix: String,
+    on_error: Option<ErrorHook>,
+    interceptors: Vec<Interceptor>,
+    authorizer: Option<Authorizer>,
+    error_template: Option<String>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    max_path_params: Option<usize>,
+    binary_media_types: Vec<String>,
+    max_response_size: usize,
+    head_from_get: bool,
+    normalize_path: bool,
+    body_config: BodyConfig,
+    total_latency: Mutex<LatencyHistogram>,
+    /// Keyed by method and route pattern
+    route_latency: Mutex<HashMap<(String, String), LatencyHistogram>>,
+    cold_initializations: AtomicUsize,
 }
 
 impl Router {
@@ -36,8 +286,33 @@ impl Router {
     pub fn new() -> Self {
         Self {
             routes: Vec::new(),
-            middlewares: vec![Arc::new(CorsMiddleware::new())],
+            middlewares: Vec::new(),
+            cors: Some(CorsConfig::new()),
             not_found_handler: None,
+            method_fallbacks: HashMap::new(),
+            absolute_redirects: false,
+            serializer: None,
+            suggest_routes: false,
+            ip_allowlist: Vec::new(),
+            ip_denylist: Vec::new(),
+            canonical_host: None,
+            health_check_paths: vec!["/health".to_string(), "/healthz".to_string()],
+            spa_fallback: None,
+            spa_api_prefix: "/api".to_string(),
+            on_error: None,
+            interceptors: Vec::new(),
+            authorizer: None,
+            error_template: None,
+            rate_limiter: None,
+            max_path_params: None,
+            binary_media_types: Vec::new(),
+            max_response_size: MAX_RESPONSE_BODY_SIZE,
+            head_from_get: false,
+            normalize_path: false,
+            body_config: BodyConfig::default(),
+            total_latency: Mutex::new(LatencyHistogram::new()),
+            route_latency: Mutex::new(HashMap::new()),
+            cold_initializations: AtomicUsize::new(0),
         }
     }
 
@@ -46,6 +321,118 @@ impl Router {
         self.middlewares.push(Arc::new(middleware));
     }
 
+    /// Add a middleware written as an async closure
+    ///
+    /// ```rust,ignore
+    /// router.use_fn(|req, next| async move {
+    ///     let response = next(req).await?;
+    ///     Ok(response.header("X-Powered-By", "lambda-router"))
+    /// });
+    /// ```
+    pub fn use_fn<F, Fut>(&mut self, middleware: F)
+    where
+        F: Fn(Request, Next) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = std::result::Result<Response, Error>> + Send + 'static,
+    {
+        self.use_middleware(MiddlewareFn::new(move |req, next| {
+            Box::pin(middleware(req, next)) as BoxFuture<'static, _>
+        }));
+    }
+
+    /// Apply `config` to the CORS headers of every response, preflights included
+    ///
+    /// CORS headers are added once the response is complete, so 404s and
+    /// errors that never reach middleware get them too. The request `Origin`
+    /// is echoed back when it's one of `config.origins`. Defaults to
+    /// `CorsConfig::new()`.
+    pub fn with_cors(&mut self, config: CorsConfig) {
+        self.cors = Some(config);
+    }
+
+    /// Stop adding CORS headers to responses, e.g. for an API only called by
+    /// other services
+    ///
+    /// Preflight requests are then routed like any other OPTIONS request.
+    pub fn without_cors(&mut self) {
+        self.cors = None;
+    }
+
+    /// Reject routes declaring more than `max` path parameters
+    ///
+    /// Applies to routes registered after this call; wildcards count as
+    /// parameters. Registering a route over the limit panics, so a typo that
+    /// introduces stray parameters fails at startup.
+    pub fn max_path_params(&mut self, max: usize) {
+        self.max_path_params = Some(max);
+    }
+
+    /// Base64-encode response bodies with these content types
+    ///
+    /// Mirrors the binary media types configured on API Gateway, which needs
+    /// binary bodies base64-encoded; `type/*` wildcards are supported.
+    /// Responses with other content types, or already marked as base64, are
+    /// passed through unchanged.
+    pub fn binary_media_types(&mut self, media_types: &[&str]) {
+        self.binary_media_types = media_types.iter().map(|t| t.to_ascii_lowercase()).collect();
+    }
+
+    /// Largest response body, in bytes, the router will return
+    ///
+    /// Measured after base64 encoding. Larger responses are replaced with a
+    /// 500 `RESPONSE_TOO_LARGE` error instead of being rejected by Lambda,
+    /// on every dispatch path and before the `on_error` hook runs. Bodies
+    /// from `Response::stream` are exempt. Defaults to `MAX_RESPONSE_BODY_SIZE`.
+    pub fn max_response_size(&mut self, bytes: usize) {
+        self.max_response_size = bytes;
+    }
+
+    /// Answer HEAD requests with a matching GET route when no HEAD route matches
+    ///
+    /// The GET handler runs as usual and its body is dropped, keeping the
+    /// status and headers. HEAD is then also listed in `Allow` headers for
+    /// paths with a GET route.
+    pub fn head_from_get(&mut self, enabled: bool) {
+        self.head_from_get = enabled;
+    }
+
+    /// Normalize request paths before matching
+    ///
+    /// Repeated slashes are collapsed and `.` / `..` segments resolved, so
+    /// `/api//users/./42` matches `/api/users/:id`. A path whose `..` segments
+    /// climb above the root is rejected with a 400.
+    pub fn normalize_path(&mut self, enabled: bool) {
+        self.normalize_path = enabled;
+    }
+
+    /// How request bodies are decoded: size limit, base64, decompression and
+    /// UTF-8 handling
+    ///
+    /// Bodies that fail to decode, or exceed `max_size`, are answered with a 400.
+    pub fn body_config(&mut self, config: BodyConfig) {
+        self.body_config = config;
+    }
+
+    /// Start a group of routes sharing `prefix` and group-level middleware
+    ///
+    /// See `RouteGroup`.
+    pub fn group(&mut self, prefix: &str) -> RouteGroup<'_> {
+        RouteGroup {
+            router: self,
+            prefix: prefix.trim_end_matches('/').to_string(),
+            middlewares: Vec::new(),
+            routes: Vec::new(),
+        }
+    }
+
+    /// Remove all routes and middleware, keeping every other setting
+    ///
+    /// Middleware is reset to the defaults of `Router::new`, i.e. none. Intended for
+    /// hot-reloading the route table in local development.
+    pub fn clear_routes(&mut self) {
+        self.routes.clear();
+        self.middlewares.clear();
+    }
+
     /// Set custom not found handler
     pub fn not_found<F>(&mut self, handler: F)
     where
@@ -54,92 +441,413 @@ impl Router {
         self.not_found_handler = Some(Arc::new(handler));
     }
 
+    /// Set a fallback handler for unmatched requests with this method
+    ///
+    /// Runs when no route matches the path, after 405 handling and the SPA
+    /// fallback but before the global not found handler.
+    pub fn fallback_for<F>(&mut self, method: &str, handler: F)
+    where
+        F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
+    {
+        self.method_fallbacks
+            .insert(method.to_uppercase(), Arc::new(handler));
+    }
+
+    /// Rewrite relative `Location` headers into absolute URLs
+    ///
+    /// The scheme and host are taken from the incoming request.
+    pub fn absolute_redirects(&mut self, enabled: bool) {
+        self.absolute_redirects = enabled;
+    }
+
+    /// Only accept requests whose source IP is inside one of these blocks
+    ///
+    /// Requests from other addresses, or without a source IP, get a 403.
+    pub fn ip_allowlist(&mut self, blocks: Vec<IpCidr>) {
+        self.ip_allowlist = blocks;
+    }
+
+    /// Reject requests whose source IP is inside one of these blocks with a 403
+    pub fn ip_denylist(&mut self, blocks: Vec<IpCidr>) {
+        self.ip_denylist = blocks;
+    }
+
+    /// Redirect (301) requests for any other host to `host`, keeping the path and query
+    ///
+    /// With `https` set, plain HTTP requests (by `X-Forwarded-Proto`) are
+    /// redirected to HTTPS as well. Health check paths are never redirected,
+    /// see `health_check_paths`.
+    pub fn canonical_host(&mut self, host: &str, https: bool) {
+        self.canonical_host = Some((host.to_string(), https));
+    }
+
+    /// Set the paths exempt from the canonical host redirect
+    ///
+    /// Defaults to `/health` and `/healthz`.
+    pub fn health_check_paths(&mut self, paths: &[&str]) {
+        self.health_check_paths = paths.iter().map(|p| p.to_string()).collect();
+    }
+
+    /// Location of the canonical URL for the request, if it isn't already canonical
+    fn canonical_redirect(&self, req: &Request) -> Option<String> {
+        let (host, https) = self.canonical_host.as_ref()?;
+        if self.health_check_paths.contains(&req.path) {
+            return None;
+        }
+
+        let scheme = req
+            .header("X-Forwarded-Proto")
+            .map(|s| s.as_str())
+            .unwrap_or("https");
+        let host_matches = req.host().is_some_and(|h| h.eq_ignore_ascii_case(host));
+        if host_matches && (!*https || scheme.eq_ignore_ascii_case("https")) {
+            return None;
+        }
+
+        let scheme = if *https { "https" } else { scheme };
+        Some(format!("{}://{}{}", scheme, host, req.path_and_query()))
+    }
+
+    /// Check the request source IP against the allow and deny lists
+    fn ip_permitted(&self, req: &Request) -> bool {
+        if self.ip_allowlist.is_empty() && self.ip_denylist.is_empty() {
+            return true;
+        }
+
+        let Some(ip) = req.source_ip() else {
+            return self.ip_allowlist.is_empty();
+        };
+
+        let allowed = self.ip_allowlist.is_empty()
+            || self.ip_allowlist.iter().any(|block| block.contains(&ip));
+        allowed && !self.ip_denylist.iter().any(|block| block.contains(&ip))
+    }
+
+    /// Serve the GET route registered at `index_path` for unmatched client-side routes
+    ///
+    /// Applies to unmatched GET requests outside the API prefix (`/api` by
+    /// default) whose last segment has no file extension. Unmatched asset-like
+    /// paths such as `/app.js` get a plain text 404 instead.
+    pub fn spa_fallback(&mut self, index_path: &str) {
+        self.spa_fallback = Some(index_path.to_string());
+    }
+
+    /// Set the path prefix excluded from the SPA fallback
+    pub fn spa_api_prefix(&mut self, prefix: &str) {
+        self.spa_api_prefix = prefix.to_string();
+    }
+
+    /// Register a hook that runs once for every failed request
+    ///
+    /// Called when routing or the handler chain returns an error, or when the
+    /// response status is 5xx. In the latter case the hook receives an
+    /// `InternalError` describing the status.
+    pub fn on_error<F>(&mut self, hook: F)
+    where
+        F: Fn(&Request, &RouterError) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Box::new(hook));
+    }
+
+    /// Register a check run on every request before routing
+    ///
+    /// Returning `Some` answers with that response without matching a route
+    /// or running middleware, e.g. for a maintenance mode; `None` continues
+    /// as usual. Interceptors run in registration order, after CORS
+    /// preflights are answered, and the first `Some` wins.
+    pub fn intercept<F>(&mut self, interceptor: F)
+    where
+        F: Fn(&Request) -> Option<Response> + Send + Sync + 'static,
+    {
+        self.interceptors.push(Box::new(interceptor));
+    }
+
+    /// Register an authorization check run for every matched route
+    ///
+    /// Runs inside the middleware chain just before the handler, so it sees
+    /// the `Context` as left by authentication middleware. Returning `false`
+    /// answers with a 403 without calling the handler.
+    pub fn authorizer<F>(&mut self, authorizer: F)
+    where
+        F: Fn(&Context, &RouteInfo) -> bool + Send + Sync + 'static,
+    {
+        self.authorizer = Some(Arc::new(authorizer));
+    }
+
+    /// Render error responses with an HTML template for clients that accept HTML
+    ///
+    /// Applies to 4xx/5xx responses when the request's `Accept` header includes
+    /// `text/html`. The placeholders `{status}`, `{message}` and `{request_id}`
+    /// are substituted, with the message HTML-escaped.
+    pub fn error_template(&mut self, html: &str) {
+        self.error_template = Some(html.to_string());
+    }
+
+    /// Limit every route to `max_requests` per `window` for each client IP
+    ///
+    /// Routes with their own `RouteHandle::rate_limit` use that limit instead.
+    /// Requests over the limit get a 429 with a `Retry-After` header.
+    pub fn rate_limit(&mut self, max_requests: u32, window: Duration) {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(max_requests, window)));
+    }
+
+    /// Include the closest registered route in 404 responses
+    ///
+    /// Only applies when no custom not found handler is set.
+    pub fn suggest_routes(&mut self, enabled: bool) {
+        self.suggest_routes = enabled;
+    }
+
+    /// Serialize JSON response bodies with a custom function
+    ///
+    /// Applied to every response whose `Content-Type` is `application/json`,
+    /// replacing the default `serde_json` output.
+    pub fn serializer<F>(&mut self, serializer: F)
+    where
+        F: Fn(&Value) -> String + Send + Sync + 'static,
+    {
+        self.serializer = Some(Box::new(serializer));
+    }
+
+    /// Create the per-route latency histograms before the first event
+    ///
+    /// Call during the Lambda init phase, after registering routes and before
+    /// `lambda_runtime::run`. Route regexes are already compiled when routes
+    /// are registered; this only allocates the histograms that would
+    /// otherwise be created by each route's first request.
+    pub fn warm_up(&self) {
+        warm_up_statics();
+
+        if let Ok(mut routes) = self.route_latency.lock() {
+            for route in &self.routes {
+                routes.entry(latency_key(route)).or_default();
+            }
+        }
+    }
+
+    /// Number of per-route latency histograms created while dispatching requests
+    ///
+    /// Stays at zero for routes registered before `warm_up` was called. Test
+    /// instrumentation for `warm_up`, not part of the public API.
+    #[doc(hidden)]
+    pub fn cold_initializations(&self) -> usize {
+        self.cold_initializations.load(Ordering::Relaxed)
+    }
+
+    /// Latency percentiles across every dispatched request
+    pub fn total_latency_percentiles(&self) -> Option<Percentiles> {
+        self.total_latency.lock().ok()?.percentiles()
+    }
+
+    /// Latency percentiles for a registered route, e.g. `GET` `/api/users/:userId`
+    ///
+    /// Each method of a pattern has its own histogram.
+    pub fn latency_percentiles(&self, method: &str, route: &str) -> Option<Percentiles> {
+        let key = (method.to_uppercase(), route.to_string());
+        self.route_latency.lock().ok()?.get(&key)?.percentiles()
+    }
+
+    fn record_latency(&self, route: Option<&Route>, elapsed: Duration) {
+        match route {
+            Some(route) => {
+                if let Ok(mut routes) = self.route_latency.lock() {
+                    let histogram = routes.entry(latency_key(route)).or_insert_with(|| {
+                        self.cold_initializations.fetch_add(1, Ordering::Relaxed);
+                        LatencyHistogram::new()
+                    });
+                    histogram.record(elapsed);
+                }
+            }
+            None => {
+                if let Ok(mut total) = self.total_latency.lock() {
+                    total.record(elapsed);
+                }
+            }
+        }
+    }
+
     /// Add a GET route
-    pub fn get<F>(&mut self, path: &str, handler: F)
+    pub fn get<F>(&mut self, path: &str, handler: F) -> RouteHandle<'_>
     where
         F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
     {
-        self.add_route("GET", path, handler);
+        self.add_route("GET", path, handler)
     }
 
     /// Add a POST route
-    pub fn post<F>(&mut self, path: &str, handler: F)
+    pub fn post<F>(&mut self, path: &str, handler: F) -> RouteHandle<'_>
     where
         F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
     {
-        self.add_route("POST", path, handler);
+        self.add_route("POST", path, handler)
     }
 
     /// Add a PUT route
-    pub fn put<F>(&mut self, path: &str, handler: F)
+    pub fn put<F>(&mut self, path: &str, handler: F) -> RouteHandle<'_>
     where
         F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
     {
-        self.add_route("PUT", path, handler);
+        self.add_route("PUT", path, handler)
     }
 
     /// Add a DELETE route
-    pub fn delete<F>(&mut self, path: &str, handler: F)
+    pub fn delete<F>(&mut self, path: &str, handler: F) -> RouteHandle<'_>
     where
         F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
     {
-        self.add_route("DELETE", path, handler);
+        self.add_route("DELETE", path, handler)
     }
 
     /// Add a PATCH route
-    pub fn patch<F>(&mut self, path: &str, handler: F)
+    pub fn patch<F>(&mut self, path: &str, handler: F) -> RouteHandle<'_>
+    where
+        F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
+    {
+        self.add_route("PATCH", path, handler)
+    }
+
+    /// Add a HEAD route
+    ///
+    /// See `head_from_get` to serve HEAD from GET routes instead.
+    pub fn head<F>(&mut self, path: &str, handler: F) -> RouteHandle<'_>
     where
         F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
     {
-        self.add_route("PATCH", path, handler);
+        self.add_route("HEAD", path, handler)
     }
 
     /// Add a route for any method
-    pub fn add_route<F>(&mut self, method: &str, path: &str, handler: F)
+    pub fn add_route<F>(&mut self, method: &str, path: &str, handler: F) -> RouteHandle<'_>
     where
         F: Fn(Request, Context) -> BoxFuture<'static, Result<Response>> + Send + Sync + 'static,
     {
+        self.add(method, path, Arc::new(handler))
+    }
+
+    /// Add a route with an already type-erased handler
+    ///
+    /// Useful when handlers are stored and registered dynamically, e.g. by a
+    /// plugin system.
+    ///
+    /// # Panics
+    /// Panics if the pattern is invalid or exceeds `max_path_params`.
+    pub fn add(&mut self, method: &str, path: &str, handler: HandlerFn) -> RouteHandle<'_> {
+        let matcher = PathMatcher::new(path);
+        if let Some(max) = self.max_path_params {
+            let count = matcher.param_names().len();
+            if count > max {
+                panic!(
+                    "Route pattern {} has {} path parameters, exceeding the limit of {}",
+                    path, count, max
+                );
+            }
+        }
+
         self.routes.push(Route {
             method: method.to_uppercase(),
-            matcher: PathMatcher::new(path),
-            handler: Arc::new(handler),
+            matcher,
+            handler,
+            consumes: Vec::new(),
+            rate_limiter: None,
+            group_middlewares: Vec::new(),
+            middlewares: Vec::new(),
+            no_store: false,
+            priority: 0,
         });
+        RouteHandle {
+            route: self.routes.last_mut().expect("route was just pushed"),
+        }
     }
 
     /// Handle incoming Lambda event
     async fn handle_request(&self, mut req: Request) -> Result<Response> {
+        // Match against the decoded path, keeping encoded slashes inside segments
+        let mut path = percent_decode(&req.path, true).ok_or_else(|| {
+            RouterError::BadRequest("Request path is not valid UTF-8".to_string())
+        })?;
+        if self.normalize_path {
+            path = normalize_path(&path).ok_or_else(|| {
+                RouterError::BadRequest("Request path escapes the root".to_string())
+            })?;
+        }
+
         // Find matching route
-        let route = self
-            .routes
-            .iter()
-            .find(|r| r.method == req.method && r.matcher.matches(&req.path).is_some());
+        let (candidates, strip_body) = self.candidates(&req.method, &path);
+
+        let content_type = req.header("Content-Type").map(|s| s.as_str());
+        let route = candidates.iter().copied().find(|r| r.accepts(content_type));
+
+        if route.is_none() && !candidates.is_empty() {
+            return Ok(Response::unsupported_media_type(&format!(
+                "Unsupported Content-Type: {}",
+                content_type.unwrap_or("none")
+            )));
+        }
 
         match route {
             Some(route) => {
                 // Extract path parameters
-                if let Some(params) = route.matcher.matches(&req.path) {
+                if let Some(params) = route_params(route, &path) {
                     req.set_path_params(params);
                 }
 
-                // Execute handler with middleware chain
-                let handler = route.handler.clone();
-                let middlewares = self.middlewares.clone();
+                let limiter = route.rate_limiter.as_ref().or(self.rate_limiter.as_ref());
+                if let Some(limiter) = limiter {
+                    let client = req.source_ip().map(|ip| ip.to_string()).unwrap_or_default();
+                    if let Err(retry_after) = limiter.check(&client) {
+                        return Ok(Response::too_many_requests("Rate limit exceeded")
+                            .retry_after(retry_after.as_secs().max(1))
+                            .short_circuit(SkipReason::RateLimited));
+                    }
+                }
 
-                // Build middleware chain by wrapping handler in middleware
-                if middlewares.is_empty() {
-                    // No middleware, just execute handler
-                    let ctx = req.context.clone();
-                    (handler)(req, ctx).await
-                } else {
-                    // Execute through middleware chain
-                    self.execute_middleware_chain(req, middlewares, handler)
-                        .await
+                let response = self.execute_route(route, req).await;
+                if strip_body {
+                    return response.map(|mut resp| {
+                        resp.body.clear();
+                        resp
+                    });
                 }
+                response
             }
             None => {
-                if let Some(handler) = &self.not_found_handler {
+                if req.method == "OPTIONS" {
+                    if let Some(response) = self.options_allow(&path) {
+                        return Ok(response);
+                    }
+                }
+
+                if let Some(response) = self.method_not_allowed(&req.method, &path) {
+                    return Ok(response);
+                }
+
+                if let Some(index_path) = &self.spa_fallback {
+                    if self.is_spa_path(&req.method, &path) {
+                        if let Some(index) = self
+                            .routes
+                            .iter()
+                            .find(|r| r.method == "GET" && r.matcher.matches(index_path).is_some())
+                        {
+                            return self.execute_route(index, req).await;
+                        }
+                    } else if req.method == "GET" && has_file_extension(&path) {
+                        return Ok(Response::new(404).text("Not Found"));
+                    }
+                }
+
+                if let Some(handler) = self.method_fallbacks.get(&req.method) {
+                    let ctx = req.context.clone();
+                    (handler)(req, ctx).await
+                } else if let Some(handler) = &self.not_found_handler {
                     let ctx = req.context.clone();
                     (handler)(req, ctx).await
+                } else if let Some(suggestion) = self.suggest_route(&req) {
+                    let message = format!(
+                        "Route not found: {} {}, did you mean {}?",
+                        req.method, req.path, suggestion
+                    );
+                    Ok(Response::not_found(&message)
+                        .with_error_field("suggestion", json!(suggestion)))
                 } else {
                     Err(RouterError::RouteNotFound {
                         method: req.method.clone(),
@@ -150,6 +858,159 @@ impl Router {
         }
     }
 
+    /// Execute a route's handler through the middleware chain
+    async fn execute_route(&self, route: &Route, mut req: Request) -> Result<Response> {
+        req.matched_route = Some(route.matcher.pattern().to_string());
+
+        // Execute handler with middleware chain
+        let mut handler = route.handler.clone();
+        if let Some(authorizer) = &self.authorizer {
+            handler = authorized_handler(authorizer.clone(), route, handler);
+        }
+        // Global, then group, then route middleware, whatever order they were added in
+        let mut middlewares = self.middlewares.clone();
+        middlewares.extend(route.group_middlewares.iter().cloned());
+        middlewares.extend(route.middlewares.iter().cloned());
+
+        let started = Instant::now();
+
+        // Build middleware chain by wrapping handler in middleware
+        let result = if middlewares.is_empty() {
+            // No middleware, just execute handler
+            let ctx = req.context.clone();
+            (handler)(req, ctx).await
+        } else {
+            // Execute through middleware chain
+            self.execute_middleware_chain(req, middlewares, handler)
+                .await
+        };
+
+        self.record_latency(Some(route), started.elapsed());
+        if route.no_store {
+            return result.map(Response::no_store);
+        }
+        result
+    }
+
+    /// Whether an unmatched request should be served by the SPA index route
+    fn is_spa_path(&self, method: &str, path: &str) -> bool {
+        let prefix = self.spa_api_prefix.trim_end_matches('/');
+        let is_api = path == prefix || path.starts_with(&format!("{}/", prefix));
+        method == "GET" && !is_api && !has_file_extension(path)
+    }
+
+    /// Routes matching `method` and the decoded `path`, by priority then specificity
+    ///
+    /// Also returns whether a HEAD request fell back to GET routes, in which
+    /// case the body must be dropped.
+    fn candidates(&self, method: &str, path: &str) -> (Vec<&Route>, bool) {
+        let find = |method: &str| -> Vec<&Route> {
+            self.routes
+                .iter()
+                .filter(|r| r.method == method && r.matcher.matches(path).is_some())
+                .collect()
+        };
+        let mut candidates = find(method);
+        let mut strip_body = false;
+        if candidates.is_empty() && self.head_from_get && method == "HEAD" {
+            candidates = find("GET");
+            strip_body = true;
+        }
+        // Highest priority, then most specific route first; registration order breaks ties
+        candidates.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then_with(|| b.matcher.specificity().cmp(a.matcher.specificity()))
+        });
+        (candidates, strip_body)
+    }
+
+    /// The route a request would be dispatched to, without running anything
+    ///
+    /// Applies the same precedence as dispatching, including path decoding
+    /// and normalization, but ignores `Content-Type` restrictions,
+    /// middleware, and fallbacks. Returns `None` when no route matches.
+    pub fn match_route(&self, method: &str, path: &str) -> Option<RouteMatch> {
+        let mut path = percent_decode(path, true)?;
+        if self.normalize_path {
+            path = normalize_path(&path)?;
+        }
+        let method = method.to_uppercase();
+        let (candidates, _) = self.candidates(&method, &path);
+        let route = candidates.first()?;
+        Some(RouteMatch {
+            route: RouteInfo {
+                method: route.method.clone(),
+                pattern: route.matcher.pattern().to_string(),
+            },
+            params: route_params(route, &path)?,
+        })
+    }
+
+    /// Methods registered for a path, along with the first matching pattern
+    fn allowed_methods(&self, path: &str) -> Option<(&str, Vec<&str>)> {
+        let mut pattern = None;
+        let mut allowed: Vec<&str> = Vec::new();
+
+        for route in self
+            .routes
+            .iter()
+            .filter(|r| r.matcher.matches(path).is_some())
+        {
+            pattern.get_or_insert(route.matcher.pattern());
+            if !allowed.contains(&route.method.as_str()) {
+                allowed.push(&route.method);
+            }
+        }
+
+        if self.head_from_get && allowed.contains(&"GET") && !allowed.contains(&"HEAD") {
+            let after_get = allowed.iter().position(|m| *m == "GET").unwrap() + 1;
+            allowed.insert(after_get, "HEAD");
+        }
+
+        pattern.map(|p| (p, allowed))
+    }
+
+    /// Answer a non-preflight OPTIONS request with the path's `Allow` header
+    fn options_allow(&self, path: &str) -> Option<Response> {
+        let (_, mut allowed) = self.allowed_methods(path)?;
+        if !allowed.contains(&"OPTIONS") {
+            allowed.push("OPTIONS");
+        }
+        Some(Response::no_content().header("Allow", allowed.join(", ")))
+    }
+
+    /// Build a 405 response when the path is registered under other methods
+    ///
+    /// The body lists the matched route pattern and the allowed methods, which
+    /// are also reported in the `Allow` header.
+    fn method_not_allowed(&self, method: &str, path: &str) -> Option<Response> {
+        let (pattern, allowed) = self.allowed_methods(path)?;
+        Some(
+            Response::method_not_allowed(&allowed)
+                .with_error_field("message", json!(format!("Method not allowed: {}", method)))
+                .with_error_field("route", json!(pattern)),
+        )
+    }
+
+    /// Find the registered route closest to the request path by edit distance
+    fn suggest_route(&self, req: &Request) -> Option<String> {
+        if !self.suggest_routes {
+            return None;
+        }
+
+        self.routes
+            .iter()
+            .map(|r| {
+                let distance = edit_distance(&req.path, r.matcher.pattern());
+                // Prefer routes registered for the requested method on ties
+                (distance, r.method != req.method, r)
+            })
+            .filter(|(distance, _, r)| *distance <= r.matcher.pattern().len() / 2)
+            .min_by_key(|(distance, other_method, _)| (*distance, *other_method))
+            .map(|(_, _, r)| format!("{} {}", r.method, r.matcher.pattern()))
+    }
+
     /// Execute request through middleware chain
     async fn execute_middleware_chain(
         &self,
@@ -196,10 +1057,248 @@ impl Router {
             });
         }
 
-        // Execute the complete chain
+        // Execute the complete chain, recovering handler errors so they keep
+        // their status code
         (current_handler)(req)
             .await
-            .map_err(|e| RouterError::HandlerError(anyhow::anyhow!("{}", e)))
+            .map_err(|e| match e.downcast::<RouterError>() {
+                Ok(e) => *e,
+                Err(e) => RouterError::HandlerError(anyhow::anyhow!("{}", e)),
+            })
+    }
+
+    /// Dispatch a raw Lambda event and return the serialized response
+    ///
+    /// The response is shaped for the event's payload format: REST API and
+    /// ALB events (top-level `httpMethod`) get `Response::to_json_v1`.
+    pub async fn dispatch(&self, event: Value) -> Value {
+        let v1 = event.get("httpMethod").is_some();
+        let response = self.respond(event).await;
+        if v1 {
+            response.to_json_v1()
+        } else {
+            response.to_json()
+        }
+    }
+
+    /// Dispatch an event straight to the matched route's handler
+    ///
+    /// Meant for benchmarks and tests that measure routing on its own.
+    /// Middleware at every level, the authorizer, interceptors, rate limits,
+    /// CORS and response post-processing are skipped, and unmatched requests
+    /// get the plain `RouteNotFound` response. Bodies that fail to decode
+    /// are still rejected with a 400, and oversized responses replaced with
+    /// a 500, as in `dispatch`.
+    pub async fn dispatch_no_middleware(&self, event: Value) -> Value {
+        let req = Request::from_lambda_event_with(event, &self.body_config);
+        if let Some(error) = req.body_error() {
+            return Response::bad_request(error).to_json();
+        }
+        let request_id = req.context.request_id.clone();
+        let response = match self.route_directly(req).await {
+            Ok(response) => response,
+            Err(e) => e.to_response(),
+        };
+        self.limit_response_size(response, &request_id).0.to_json()
+    }
+
+    async fn route_directly(&self, mut req: Request) -> Result<Response> {
+        let mut path = percent_decode(&req.path, true).ok_or_else(|| {
+            RouterError::BadRequest("Request path is not valid UTF-8".to_string())
+        })?;
+        if self.normalize_path {
+            path = normalize_path(&path).ok_or_else(|| {
+                RouterError::BadRequest("Request path escapes the root".to_string())
+            })?;
+        }
+
+        let (candidates, _) = self.candidates(&req.method, &path);
+        let Some(route) = candidates.first() else {
+            return Err(RouterError::RouteNotFound {
+                method: req.method.clone(),
+                path: req.path.clone(),
+            });
+        };
+        if let Some(params) = route_params(route, &path) {
+            req.set_path_params(params);
+        }
+        req.matched_route = Some(route.matcher.pattern().to_string());
+
+        let ctx = req.context.clone();
+        (route.handler)(req, ctx).await
+    }
+
+    /// Handle a raw Lambda event, applying every router-level step
+    async fn respond(&self, event: Value) -> Response {
+        // Parse request
+        let req = Request::from_lambda_event_with(event, &self.body_config);
+
+        let origin = self
+            .cors
+            .as_ref()
+            .and_then(|_| req.origin().map(|o| o.to_string()));
+        let with_cors = |response: Response| match &self.cors {
+            Some(cors) => cors.apply(response, origin.as_deref()),
+            None => response,
+        };
+
+        if !self.ip_permitted(&req) {
+            return with_cors(Response::forbidden("Access denied for source IP"));
+        }
+
+        if let Some(error) = req.body_error() {
+            return with_cors(Response::bad_request(error));
+        }
+
+        // Handle CORS preflight early; bare OPTIONS requests are routed normally.
+        // Without a CORS config preflights are routed like any OPTIONS request.
+        if self.cors.is_some() && req.is_preflight() {
+            return with_cors(Response::cors_preflight());
+        }
+
+        if let Some(location) = self.canonical_redirect(&req) {
+            return with_cors(Response::moved_permanently(&location));
+        }
+
+        let base_url = self.absolute_redirects.then(|| req.full_url());
+        let original = self.on_error.as_ref().map(|_| req.clone());
+        let wants_html = req
+            .header("Accept")
+            .is_some_and(|accept| accept.contains("text/html"));
+        let request_id = req.context.request_id.clone();
+        let started = Instant::now();
+
+        // Route request
+        let intercepted = self
+            .interceptors
+            .iter()
+            .find_map(|intercept| intercept(&req));
+        let result = match intercepted {
+            Some(response) => Ok(response),
+            None => self.handle_request(req).await,
+        };
+
+        let (mut response, mut error) = match result {
+            Ok(resp) => (resp, None),
+            Err(e) => (e.to_response(), Some(e)),
+        };
+        if response.is_client_error() || response.is_server_error() {
+            response = response.with_error_field("requestId", json!(request_id));
+        }
+
+        self.record_latency(None, started.elapsed());
+
+        if let Some(base_url) = base_url {
+            response = response.absolute_location(&base_url);
+        }
+
+        if let Some(template) = &self.error_template {
+            if wants_html && response.status_code >= 400 {
+                response = render_error_template(template, response, &request_id);
+            }
+        }
+
+        // A streamed body isn't known yet, so it can't be post-processed
+        if !response.is_streaming() {
+            if let Some(serializer) = &self.serializer {
+                response = reserialize_body(response, serializer);
+            }
+
+            if !self.binary_media_types.is_empty() {
+                response = encode_binary_body(response, &self.binary_media_types);
+            }
+        }
+
+        let (response, too_large) = self.limit_response_size(response, &request_id);
+        error = too_large.or(error);
+
+        // Runs last so the hook also sees responses replaced above
+        if let (Some(hook), Some(req)) = (&self.on_error, &original) {
+            match &error {
+                Some(e) => hook(req, e),
+                None if response.status_code >= 500 => hook(
+                    req,
+                    &RouterError::InternalError(format!(
+                        "Handler responded with status {}",
+                        response.status_code
+                    )),
+                ),
+                None => {}
+            }
+        }
+
+        with_cors(response)
+    }
+
+    /// Replace a response over `max_response_size` with a 500 `RESPONSE_TOO_LARGE`
+    ///
+    /// Shared by every dispatch path. Returns the error for the `on_error`
+    /// hook when the response was replaced. Streamed bodies aren't known
+    /// yet and are never limited.
+    fn limit_response_size(
+        &self,
+        response: Response,
+        request_id: &str,
+    ) -> (Response, Option<RouterError>) {
+        if response.is_streaming() || response.body.len() <= self.max_response_size {
+            return (response, None);
+        }
+        tracing::error!(
+            request_id = %request_id,
+            body_bytes = response.body.len(),
+            limit_bytes = self.max_response_size,
+            "response body exceeds the maximum allowed size"
+        );
+        let error = RouterError::InternalError(format!(
+            "Response body of {} bytes exceeds the {} byte limit",
+            response.body.len(),
+            self.max_response_size
+        ));
+        let response = Response::internal_error("Response body exceeds the maximum allowed size")
+            .with_error_field("code", json!("RESPONSE_TOO_LARGE"))
+            .with_error_field("requestId", json!(request_id));
+        (response, Some(error))
+    }
+
+    /// Dispatch several events, e.g. sub-requests fanned out from one invocation
+    ///
+    /// At most `max_concurrency` events are in flight at once (all of them
+    /// when `None`), and a new one starts as soon as the oldest is done.
+    /// Responses are returned in the same order as `events`.
+    ///
+    /// All sub-requests run on the calling task, interleaving at `.await`
+    /// points, so CPU-bound handlers never run in parallel.
+    pub async fn dispatch_batch(
+        &self,
+        events: Vec<Value>,
+        max_concurrency: Option<usize>,
+    ) -> Vec<Value> {
+        use futures::stream::{self, StreamExt};
+
+        let limit = max_concurrency.unwrap_or(events.len()).max(1);
+        stream::iter(events)
+            .map(|event| self.dispatch(event))
+            .buffered(limit)
+            .collect()
+            .await
+    }
+
+    /// Convert router into a Lambda service function using response streaming
+    ///
+    /// Bodies from `Response::stream` are sent as they are produced, lifting
+    /// the 6 MB limit; other responses are sent as a single chunk. Run it
+    /// with `lambda_runtime::run` on a function configured for streaming,
+    /// e.g. a function URL with `InvokeMode: RESPONSE_STREAM`.
+    pub fn into_streaming_service(self) -> impl Fn(LambdaEvent<Value>) -> StreamingResponseFuture {
+        let router = Arc::new(self);
+
+        move |event: LambdaEvent<Value>| {
+            let router = router.clone();
+            Box::pin(async move {
+                let (event_payload, _context) = event.into_parts();
+                into_stream_response(router.respond(event_payload).await)
+            })
+        }
     }
 
     /// Convert router into Lambda service function
@@ -212,25 +1311,226 @@ impl Router {
             let router = router.clone();
             Box::pin(async move {
                 let (event_payload, _context) = event.into_parts();
+                Ok(router.dispatch(event_payload).await)
+            })
+        }
+    }
+}
 
-                // Parse request
-                let req = Request::from_lambda_event(event_payload);
+/// Split a response into the metadata prelude and body stream Lambda expects
+fn into_stream_response(
+    response: Response,
+) -> std::result::Result<StreamResponse<ResponseStream>, Error> {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
 
-                // Handle CORS preflight early
-                if req.is_preflight() {
-                    return Ok(Response::cors_preflight().to_json());
-                }
+    let mut headers = HeaderMap::new();
+    for (name, value) in &response.headers {
+        headers.insert(
+            HeaderName::from_bytes(name.as_bytes())?,
+            HeaderValue::from_str(value)?,
+        );
+    }
+    let metadata_prelude = MetadataPrelude {
+        status_code: StatusCode::from_u16(response.status_code)?,
+        headers,
+        cookies: response.cookies.clone(),
+    };
 
-                // Route request
-                let response = match router.handle_request(req).await {
-                    Ok(resp) => resp,
-                    Err(e) => e.to_response(),
-                };
+    let stream: ResponseStream = match response.take_stream() {
+        Some(stream) => stream.map(Ok).boxed(),
+        None => {
+            let body = if response.is_base64_encoded {
+                STANDARD.decode(&response.body)?
+            } else {
+                response.body.into_bytes()
+            };
+            futures::stream::once(async move { Ok(Bytes::from(body)) }).boxed()
+        }
+    };
 
-                Ok(response.to_json())
+    Ok(StreamResponse {
+        metadata_prelude,
+        stream,
+    })
+}
+
+/// Key of a route's latency histogram
+fn latency_key(route: &Route) -> (String, String) {
+    (route.method.clone(), route.matcher.pattern().to_string())
+}
+
+/// Path parameters for a matching route, with the escapes the path kept decoded
+fn route_params(route: &Route, path: &str) -> Option<HashMap<String, String>> {
+    let params = route.matcher.matches(path)?;
+    Some(
+        params
+            .into_iter()
+            .map(|(k, v)| match percent_decode(&v, false) {
+                Some(decoded) => (k, decoded),
+                None => (k, v),
             })
+            .collect(),
+    )
+}
+
+/// Compare a media type against an accepted type, supporting `type/*`
+fn media_type_matches(accepted: &str, media_type: &str) -> bool {
+    match accepted.strip_suffix("/*") {
+        Some(prefix) => media_type
+            .split_once('/')
+            .is_some_and(|(kind, _)| kind.eq_ignore_ascii_case(prefix)),
+        None => accepted.eq_ignore_ascii_case(media_type),
+    }
+}
+
+/// Base64-encode the body if its content type is one of `media_types`
+fn encode_binary_body(mut response: Response, media_types: &[String]) -> Response {
+    use base64::Engine;
+
+    if response.is_base64_encoded {
+        return response;
+    }
+    let content_type = response
+        .headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        .map(|(_, v)| v.split(';').next().unwrap_or("").trim().to_string());
+    let Some(content_type) = content_type else {
+        return response;
+    };
+
+    if media_types
+        .iter()
+        .any(|t| media_type_matches(t, &content_type))
+    {
+        response.body = base64::engine::general_purpose::STANDARD.encode(response.body.as_bytes());
+        response.is_base64_encoded = true;
+    }
+    response
+}
+
+/// Wrap a handler so it only runs when the authorizer allows the request
+fn authorized_handler(authorizer: Authorizer, route: &Route, handler: HandlerFn) -> HandlerFn {
+    let info = RouteInfo {
+        method: route.method.clone(),
+        pattern: route.matcher.pattern().to_string(),
+    };
+    Arc::new(move |req: Request, ctx: Context| {
+        if authorizer(&ctx, &info) {
+            handler(req, ctx)
+        } else {
+            Box::pin(async {
+                Ok(Response::forbidden("Not authorized for this route")
+                    .short_circuit(SkipReason::Forbidden))
+            })
+        }
+    })
+}
+
+/// Prepend a group prefix to a route path, e.g. `/api/v1` + `/users`
+fn join_prefix(prefix: &str, path: &str) -> String {
+    match path.trim_start_matches('/') {
+        "" if prefix.is_empty() => "/".to_string(),
+        "" => prefix.to_string(),
+        rest => format!("{}/{}", prefix, rest),
+    }
+}
+
+/// Collapse repeated slashes and resolve `.` and `..` segments
+///
+/// Returns None if `..` would climb above the root. A trailing slash is kept.
+fn normalize_path(path: &str) -> Option<String> {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let mut normalized = format!("/{}", segments.join("/"));
+    if path.ends_with('/') && !segments.is_empty() {
+        normalized.push('/');
+    }
+    Some(normalized)
+}
+
+/// Whether the last path segment looks like a file name, e.g. `app.css`
+fn has_file_extension(path: &str) -> bool {
+    path.rsplit('/')
+        .next()
+        .and_then(|segment| segment.rsplit_once('.'))
+        .is_some_and(|(name, ext)| !name.is_empty() && !ext.is_empty())
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        prev = curr;
+    }
+
+    prev[b.len()]
+}
+
+/// Replace an error response body with the rendered HTML template
+fn render_error_template(template: &str, response: Response, request_id: &str) -> Response {
+    let message = serde_json::from_str::<Value>(&response.body)
+        .ok()
+        .and_then(|body| {
+            let message = body["error"]["message"]
+                .as_str()
+                .or(body["message"].as_str());
+            message.map(|m| m.to_string())
+        })
+        .unwrap_or_else(|| response.body.clone());
+
+    let html = template
+        .replace("{status}", &response.status_code.to_string())
+        .replace("{message}", &escape_html(&message))
+        .replace("{request_id}", &escape_html(request_id));
+
+    let mut response = response.text(html);
+    response.headers.insert(
+        "Content-Type".to_string(),
+        "text/html; charset=utf-8".to_string(),
+    );
+    response
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Re-serialize a JSON response body with a custom serializer
+fn reserialize_body(mut response: Response, serializer: &BodySerializer) -> Response {
+    let is_json = response
+        .headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        .is_some_and(|(_, ct)| ct.starts_with("application/json"));
+
+    if is_json {
+        if let Ok(value) = serde_json::from_str::<Value>(&response.body) {
+            response.body = serializer(&value);
         }
     }
+    response
 }
 
 impl Default for Router {
@@ -240,9 +1540,23 @@ impl Default for Router {
 }
 
 /// Helper macro for creating async handlers
+///
+/// The handler may return `Result<Response, E>` for any error type `E`
+/// implementing `IntoResponse`.
 #[macro_export]
 macro_rules! handler {
     ($func:expr) => {
-        |req: Request, ctx: Context| Box::pin($func(req, ctx))
+        |req: Request, ctx: Context| {
+            let fut = $func(req, ctx);
+            Box::pin(async move { $crate::router::handler_result(fut.await) })
+        }
     };
 }
+
+/// Converts a handler's error into a `RouterError`; used by `handler!`
+#[doc(hidden)]
+pub fn handler_result<E: IntoResponse>(
+    result: std::result::Result<Response, E>,
+) -> Result<Response> {
+    result.map_err(IntoResponse::into_router_error)
+}
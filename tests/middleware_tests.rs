@@ -1,9 +1,15 @@
 //! Tests for middleware functionality
 
-use aws_lambda_router::{Middleware, Request, Response};
+use aws_lambda_router::middleware::{
+    negotiate_encoding, CompressionMiddleware, CorsMiddleware, ETagAlgorithm, ETagMiddleware,
+    Encoding, JwtAuthMiddleware, JwtClaims, LoggingMiddleware, PaginationLinkMiddleware,
+    RequireHeadersMiddleware, RetryMiddleware, TimeoutMiddleware,
+};
+use aws_lambda_router::{CorsConfig, Middleware, Request, Response, SkipReason};
 use async_trait::async_trait;
 use lambda_runtime::Error;
 use serde_json::json;
+use std::time::Duration;
 
 /// Helper to create a mock request
 fn mock_request(method: &str, path: &str) -> Request {
@@ -58,13 +64,29 @@ fn test_custom_middleware_creation() {
 
 #[test]
 fn test_request_is_preflight() {
-    let req = mock_request("OPTIONS", "/api/users");
+    let mut req = mock_request("OPTIONS", "/api/users");
+    req.headers.insert("access-control-request-method".to_string(), "POST".to_string());
     assert!(req.is_preflight());
     
     let req = mock_request("GET", "/api/users");
     assert!(!req.is_preflight());
 }
 
+#[test]
+fn test_bare_options_is_not_preflight() {
+    let req = mock_request("OPTIONS", "/api/users");
+    assert!(!req.is_preflight());
+}
+
+#[test]
+fn test_request_is_cors_request() {
+    let mut req = mock_request("GET", "/api/users");
+    assert!(!req.is_cors_request());
+
+    req.headers.insert("origin".to_string(), "https://example.com".to_string());
+    assert!(req.is_cors_request());
+}
+
 #[test]
 fn test_cors_preflight_response() {
     let response = Response::cors_preflight();
@@ -73,3 +95,587 @@ fn test_cors_preflight_response() {
     assert!(response.headers.contains_key("Access-Control-Allow-Methods"));
     assert!(response.headers.contains_key("Access-Control-Allow-Headers"));
 }
+
+/// Next function that always responds 200
+fn ok_next() -> aws_lambda_router::Next {
+    Box::new(|_req: Request| Box::pin(async { Ok(Response::ok(json!({}))) }))
+}
+
+#[tokio::test]
+async fn test_require_headers_missing() {
+    let middleware = RequireHeadersMiddleware::new(&["X-Api-Key", "X-Tenant"]);
+    let req = mock_request("GET", "/api/users");
+
+    let response = middleware.handle(req, ok_next()).await.unwrap();
+    assert_eq!(response.status_code, 400);
+    assert!(response.body.contains("X-Api-Key, X-Tenant"));
+}
+
+#[tokio::test]
+async fn test_require_headers_present() {
+    let middleware = RequireHeadersMiddleware::new(&["X-Api-Key"]);
+    let mut req = mock_request("GET", "/api/users");
+    req.headers.insert("x-api-key".to_string(), "secret".to_string());
+
+    let response = middleware.handle(req, ok_next()).await.unwrap();
+    assert_eq!(response.status_code, 200);
+}
+
+/// Next function that always responds with the same JSON body
+fn fixed_body_next() -> aws_lambda_router::Next {
+    Box::new(|_req: Request| Box::pin(async { Ok(Response::ok(json!({"id": 1}))) }))
+}
+
+#[tokio::test]
+async fn test_etag_identical_bodies() {
+    let middleware = ETagMiddleware::new();
+
+    let first = middleware
+        .handle(mock_request("GET", "/api/items/1"), fixed_body_next())
+        .await
+        .unwrap();
+    let second = middleware
+        .handle(mock_request("GET", "/api/items/1"), fixed_body_next())
+        .await
+        .unwrap();
+
+    let etag = first.headers.get("ETag").unwrap();
+    assert!(etag.starts_with('"') && etag.len() == 42);
+    assert_eq!(Some(etag), second.headers.get("ETag"));
+}
+
+#[tokio::test]
+async fn test_etag_if_none_match() {
+    let middleware = ETagMiddleware::new()
+        .algorithm(ETagAlgorithm::Fnv1a)
+        .weak(true);
+    let etag = middleware.compute(r#"{"id":1}"#);
+    assert!(etag.starts_with("W/\""));
+
+    let mut req = mock_request("GET", "/api/items/1");
+    req.headers.insert("if-none-match".to_string(), etag.clone());
+
+    let response = middleware.handle(req, fixed_body_next()).await.unwrap();
+    assert_eq!(response.status_code, 304);
+    assert!(response.body.is_empty());
+    assert_eq!(response.headers.get("ETag"), Some(&etag));
+}
+
+/// Next function returning a JSON body large enough to compress
+fn large_body_next() -> aws_lambda_router::Next {
+    Box::new(|_req: Request| {
+        Box::pin(async {
+            let items: Vec<_> = (0..200).map(|id| json!({ "id": id, "name": "item" })).collect();
+            Ok(Response::ok(json!(items)))
+        })
+    })
+}
+
+fn accepting(encodings: &str) -> Request {
+    let mut req = mock_request("GET", "/api/items");
+    req.headers.insert("accept-encoding".to_string(), encodings.to_string());
+    req
+}
+
+fn decode_body(response: &Response) -> Vec<u8> {
+    use base64::Engine;
+    assert!(response.is_base64_encoded);
+    base64::engine::general_purpose::STANDARD.decode(&response.body).unwrap()
+}
+
+#[test]
+fn test_negotiate_encoding() {
+    assert_eq!(negotiate_encoding("gzip, deflate"), Some(Encoding::Gzip));
+    let preferred = if cfg!(feature = "brotli") { "br" } else { "gzip" };
+    assert_eq!(negotiate_encoding("*").map(|e| e.as_str()), Some(preferred));
+    assert_eq!(negotiate_encoding("gzip;q=0, identity"), None);
+    assert_eq!(negotiate_encoding("deflate"), None);
+}
+
+#[tokio::test]
+async fn test_compression_gzip() {
+    use std::io::Read;
+
+    let middleware = CompressionMiddleware::new();
+    let response = middleware.handle(accepting("gzip, deflate"), large_body_next()).await.unwrap();
+    assert_eq!(response.headers.get("Content-Encoding"), Some(&"gzip".to_string()));
+    assert_eq!(response.headers.get("Vary"), Some(&"Accept-Encoding".to_string()));
+
+    let compressed = decode_body(&response);
+    let mut body = String::new();
+    flate2::read::GzDecoder::new(&compressed[..]).read_to_string(&mut body).unwrap();
+    let items: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(items.as_array().unwrap().len(), 200);
+
+    // Small bodies and clients without a supported coding get the plain body
+    let response = middleware.handle(accepting("gzip"), ok_next()).await.unwrap();
+    assert!(!response.is_base64_encoded);
+    let response = middleware.handle(accepting("identity"), large_body_next()).await.unwrap();
+    assert!(!response.is_base64_encoded);
+    assert!(!response.headers.contains_key("Content-Encoding"));
+}
+
+#[cfg(feature = "brotli")]
+#[tokio::test]
+async fn test_compression_prefers_brotli() {
+    use std::io::Read;
+
+    let middleware = CompressionMiddleware::new();
+    let response = middleware
+        .handle(accepting("gzip, deflate, br"), large_body_next())
+        .await
+        .unwrap();
+    assert_eq!(response.headers.get("Content-Encoding"), Some(&"br".to_string()));
+
+    let compressed = decode_body(&response);
+    let mut body = String::new();
+    brotli::Decompressor::new(&compressed[..], 4096).read_to_string(&mut body).unwrap();
+    let items: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(items.as_array().unwrap().len(), 200);
+
+    // An explicit refusal falls back to gzip
+    let response = middleware
+        .handle(accepting("br;q=0, gzip"), large_body_next())
+        .await
+        .unwrap();
+    assert_eq!(response.headers.get("Content-Encoding"), Some(&"gzip".to_string()));
+}
+
+/// Next function that takes longer than any test timeout
+fn slow_next() -> aws_lambda_router::Next {
+    Box::new(|_req: Request| {
+        Box::pin(async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok(Response::ok(json!({})))
+        })
+    })
+}
+
+#[tokio::test]
+async fn test_timeout_slow_handler_gets_504() {
+    let middleware = TimeoutMiddleware::new(Duration::from_millis(10));
+
+    let response = middleware
+        .handle(mock_request("GET", "/api/slow"), slow_next())
+        .await
+        .unwrap();
+    assert_eq!(response.status_code, 504);
+}
+
+#[tokio::test]
+async fn test_timeout_fast_handler_passes_through() {
+    let middleware = TimeoutMiddleware::new(Duration::from_millis(500));
+    let fast_next: aws_lambda_router::Next = Box::new(|_req: Request| {
+        Box::pin(async { Ok(Response::created(json!({"id": 1}))) })
+    });
+
+    let response = middleware
+        .handle(mock_request("POST", "/api/items"), fast_next)
+        .await
+        .unwrap();
+    assert_eq!(response.status_code, 201);
+    assert_eq!(response.body, r#"{"id":1}"#);
+
+    // Errors finishing in time are propagated, not turned into a timeout
+    let failing_next: aws_lambda_router::Next =
+        Box::new(|_req: Request| Box::pin(async { Err(Error::from("boom")) }));
+    let result = middleware.handle(mock_request("POST", "/api/items"), failing_next).await;
+    assert_eq!(result.unwrap_err().to_string(), "boom");
+}
+
+#[tokio::test]
+async fn test_timeout_custom_response() {
+    let middleware = TimeoutMiddleware::new(Duration::from_millis(10)).on_timeout(|| {
+        Response::new(503)
+            .json(json!({"error": "Service Unavailable"}))
+            .header("Retry-After", "5")
+    });
+
+    let response = middleware
+        .handle(mock_request("GET", "/api/slow"), slow_next())
+        .await
+        .unwrap();
+    assert_eq!(response.status_code, 503);
+    assert_eq!(response.headers.get("Retry-After"), Some(&"5".to_string()));
+}
+
+#[tokio::test]
+async fn test_retry_until_success() {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    let calls = Arc::new(AtomicU32::new(0));
+    let counter = calls.clone();
+    let flaky: aws_lambda_router::Next = Box::new(move |_req: Request| {
+        let attempt = counter.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move {
+            match attempt {
+                0 => Err("connection reset".into()),
+                1 => Ok(Response::new(503)),
+                _ => Ok(Response::ok(json!({"attempt": attempt}))),
+            }
+        })
+    });
+
+    let middleware = RetryMiddleware::new(3).backoff(Duration::from_millis(1));
+    let response = middleware
+        .handle(mock_request("GET", "/api/flaky"), flaky)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status_code, 200);
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn test_retry_gives_up() {
+    let unavailable: aws_lambda_router::Next =
+        Box::new(|_req: Request| Box::pin(async { Ok(Response::new(503)) }));
+
+    let middleware = RetryMiddleware::new(2).backoff(Duration::from_millis(1));
+    let response = middleware
+        .handle(mock_request("GET", "/api/down"), unavailable)
+        .await
+        .unwrap();
+    assert_eq!(response.status_code, 503);
+}
+
+#[tokio::test]
+async fn test_pagination_link_header() {
+    let mut req = mock_request("GET", "/api/users");
+    req.headers.insert("host".to_string(), "api.example.com".to_string());
+    req.query_params.insert("page".to_string(), "2".to_string());
+    req.query_params.insert("limit".to_string(), "10".to_string());
+    req.query_params.insert("sort".to_string(), "name".to_string());
+
+    let paginated: aws_lambda_router::Next = Box::new(|_req: Request| {
+        Box::pin(async {
+            Ok(Response::ok(json!({
+                "items": [],
+                "pagination": { "page": 2, "limit": 10, "total": 35 }
+            })))
+        })
+    });
+
+    let response = PaginationLinkMiddleware.handle(req, paginated).await.unwrap();
+    let link = response.headers.get("Link").unwrap();
+    let base = "https://api.example.com/api/users?sort=name";
+    assert_eq!(
+        link,
+        &format!(
+            "<{base}&page=1&limit=10>; rel=\"first\", <{base}&page=1&limit=10>; rel=\"prev\", \
+             <{base}&page=3&limit=10>; rel=\"next\", <{base}&page=4&limit=10>; rel=\"last\""
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_pagination_link_skips_unpaginated() {
+    let response = PaginationLinkMiddleware
+        .handle(mock_request("GET", "/api/users/1"), fixed_body_next())
+        .await
+        .unwrap();
+    assert!(!response.headers.contains_key("Link"));
+}
+
+#[tokio::test]
+async fn test_outer_middleware_header_wins() {
+    let inner = std::sync::Arc::new(TestMiddleware::new("Cache-Control", "no-cache"));
+    let outer = TestMiddleware::new("cache-control", "max-age=60");
+
+    let next: aws_lambda_router::Next = Box::new(move |req: Request| {
+        let inner = inner.clone();
+        Box::pin(async move { inner.handle(req, ok_next()).await })
+    });
+
+    let response = outer.handle(mock_request("GET", "/api/users"), next).await.unwrap();
+    let values: Vec<_> = response
+        .headers
+        .iter()
+        .filter(|(k, _)| k.eq_ignore_ascii_case("cache-control"))
+        .map(|(_, v)| v.as_str())
+        .collect();
+    assert_eq!(values, vec!["max-age=60"]);
+}
+
+/// Middleware rejecting requests without an Authorization header
+struct RejectingAuth;
+
+#[async_trait]
+impl Middleware for RejectingAuth {
+    async fn handle(&self, req: Request, next: aws_lambda_router::Next) -> Result<Response, Error> {
+        if req.header("Authorization").is_none() {
+            return Ok(Response::unauthorized("Missing token").short_circuit(SkipReason::Unauthorized));
+        }
+        next(req).await
+    }
+}
+
+/// Middleware recording the short-circuit reason of each response
+struct RecordingMiddleware {
+    reasons: std::sync::Arc<std::sync::Mutex<Vec<Option<SkipReason>>>>,
+}
+
+#[async_trait]
+impl Middleware for RecordingMiddleware {
+    async fn handle(&self, req: Request, next: aws_lambda_router::Next) -> Result<Response, Error> {
+        let response = next(req).await?;
+        self.reasons.lock().unwrap().push(response.short_circuit_reason().cloned());
+        Ok(response)
+    }
+}
+
+#[tokio::test]
+async fn test_short_circuit_reason_recorded() {
+    let reasons = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let recorder = RecordingMiddleware { reasons: reasons.clone() };
+    let auth = std::sync::Arc::new(RejectingAuth);
+
+    let chain = |auth: std::sync::Arc<RejectingAuth>| -> aws_lambda_router::Next {
+        Box::new(move |req: Request| {
+            let auth = auth.clone();
+            Box::pin(async move { auth.handle(req, ok_next()).await })
+        })
+    };
+
+    let response = recorder.handle(mock_request("GET", "/api/users"), chain(auth.clone())).await.unwrap();
+    assert_eq!(response.status_code, 401);
+
+    let mut req = mock_request("GET", "/api/users");
+    req.headers.insert("authorization".to_string(), "Bearer token".to_string());
+    let response = recorder.handle(req, chain(auth)).await.unwrap();
+    assert_eq!(response.status_code, 200);
+
+    assert_eq!(*reasons.lock().unwrap(), vec![Some(SkipReason::Unauthorized), None]);
+}
+
+#[tokio::test]
+async fn test_require_headers_short_circuit_reason() {
+    let middleware = RequireHeadersMiddleware::new(&["X-Api-Key"]);
+    let response = middleware.handle(mock_request("GET", "/api/users"), ok_next()).await.unwrap();
+    assert_eq!(response.short_circuit_reason(), Some(&SkipReason::InvalidRequest));
+}
+
+#[tokio::test]
+async fn test_cors_per_origin_credentials() {
+    let middleware = CorsMiddleware::from_config(
+        CorsConfig::new()
+            .origin("https://app.example.com", true)
+            .origin("https://partner.example.org", false),
+    );
+
+    let request_from = |origin: &str| {
+        let mut req = mock_request("GET", "/api/users");
+        req.headers.insert("origin".to_string(), origin.to_string());
+        req
+    };
+
+    let response = middleware.handle(request_from("https://app.example.com"), ok_next()).await.unwrap();
+    assert_eq!(
+        response.headers.get("Access-Control-Allow-Origin"),
+        Some(&"https://app.example.com".to_string())
+    );
+    assert_eq!(
+        response.headers.get("Access-Control-Allow-Credentials"),
+        Some(&"true".to_string())
+    );
+    assert_eq!(response.headers.get("Vary"), Some(&"Origin".to_string()));
+
+    let response = middleware.handle(request_from("https://partner.example.org"), ok_next()).await.unwrap();
+    assert_eq!(
+        response.headers.get("Access-Control-Allow-Origin"),
+        Some(&"https://partner.example.org".to_string())
+    );
+    assert!(!response.headers.contains_key("Access-Control-Allow-Credentials"));
+
+    // The listed origins are an allowlist, so the `*` default is not used
+    let response = middleware.handle(request_from("https://evil.example.net"), ok_next()).await.unwrap();
+    assert!(!response.headers.contains_key("Access-Control-Allow-Origin"));
+    assert!(!response.headers.contains_key("Access-Control-Allow-Credentials"));
+}
+
+const JWT_SECRET: &[u8] = b"test-secret";
+
+fn jwt(claims: serde_json::Value, secret: &[u8]) -> String {
+    jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &claims,
+        &jsonwebtoken::EncodingKey::from_secret(secret),
+    )
+    .unwrap()
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn request_with_token(token: &str) -> Request {
+    let mut req = mock_request("GET", "/api/me");
+    req.headers
+        .insert("authorization".to_string(), format!("Bearer {}", token));
+    req
+}
+
+/// Next that echoes what the middleware put in the context
+fn context_echo_next() -> aws_lambda_router::Next {
+    Box::new(|req: Request| {
+        Box::pin(async move {
+            let claims = req.context.get_extension::<JwtClaims>().map(|c| c.0.clone());
+            Ok(Response::ok(json!({
+                "userId": req.context.user_id,
+                "email": req.context.email,
+                "token": req.context.auth_token,
+                "claims": claims,
+            })))
+        })
+    })
+}
+
+#[tokio::test]
+async fn test_jwt_auth_valid_token() {
+    let middleware = JwtAuthMiddleware::hmac(JWT_SECRET);
+    let token = jwt(
+        json!({"sub": "user-1", "email": "a@example.com", "exp": unix_now() + 600}),
+        JWT_SECRET,
+    );
+
+    let response = middleware.handle(request_with_token(&token), context_echo_next()).await.unwrap();
+    assert_eq!(response.status_code, 200);
+    let body: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+    assert_eq!(body["userId"], "user-1");
+    assert_eq!(body["email"], "a@example.com");
+    assert_eq!(body["token"], token.as_str());
+    assert_eq!(body["claims"]["sub"], "user-1");
+}
+
+#[tokio::test]
+async fn test_jwt_auth_expired_token() {
+    let middleware = JwtAuthMiddleware::hmac(JWT_SECRET);
+    let token = jwt(json!({"sub": "user-1", "exp": unix_now() - 3600}), JWT_SECRET);
+
+    let response = middleware.handle(request_with_token(&token), context_echo_next()).await.unwrap();
+    assert_eq!(response.status_code, 401);
+    assert!(response.body.contains("expired"));
+    assert_eq!(response.short_circuit_reason(), Some(&SkipReason::Unauthorized));
+}
+
+#[tokio::test]
+async fn test_jwt_auth_missing_or_invalid_token() {
+    let middleware = JwtAuthMiddleware::hmac(JWT_SECRET);
+
+    let response = middleware.handle(mock_request("GET", "/api/me"), context_echo_next()).await.unwrap();
+    assert_eq!(response.status_code, 401);
+    assert!(response.body.contains("Missing bearer token"));
+
+    let forged = jwt(json!({"sub": "user-1", "exp": unix_now() + 600}), b"other-secret");
+    let response = middleware.handle(request_with_token(&forged), context_echo_next()).await.unwrap();
+    assert_eq!(response.status_code, 401);
+    assert!(response.body.contains("Invalid token"));
+}
+
+#[tokio::test]
+async fn test_jwt_auth_custom_claims_mapping() {
+    let middleware = JwtAuthMiddleware::hmac(JWT_SECRET)
+        .issuer("https://auth.example.com")
+        .map_claims(|claims, ctx| {
+            ctx.user_id = claims["uid"].as_str().map(|s| s.to_string());
+        });
+
+    let token = jwt(
+        json!({"uid": "u-9", "iss": "https://auth.example.com", "exp": unix_now() + 600}),
+        JWT_SECRET,
+    );
+    let response = middleware.handle(request_with_token(&token), context_echo_next()).await.unwrap();
+    let body: serde_json::Value = serde_json::from_str(&response.body).unwrap();
+    assert_eq!(body["userId"], "u-9");
+    assert!(body["email"].is_null());
+
+    let wrong_issuer = jwt(
+        json!({"uid": "u-9", "iss": "https://evil.example.com", "exp": unix_now() + 600}),
+        JWT_SECRET,
+    );
+    let response = middleware.handle(request_with_token(&wrong_issuer), context_echo_next()).await.unwrap();
+    assert_eq!(response.status_code, 401);
+}
+
+/// Writer collecting `tracing` output for assertions
+#[derive(Clone, Default)]
+struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CapturedLogs {
+    fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn test_logging_middleware_passes_response_through() {
+    let middleware = LoggingMiddleware::new()
+        .level(tracing::Level::DEBUG)
+        .include_query(true);
+    let next: aws_lambda_router::Next = Box::new(|_req: Request| {
+        Box::pin(async {
+            Ok(Response::created(json!({"id": 1})).header("X-Custom", "kept"))
+        })
+    });
+
+    let response = middleware.handle(mock_request("POST", "/api/users"), next).await.unwrap();
+    assert_eq!(response.status_code, 201);
+    assert_eq!(response.body, r#"{"id":1}"#);
+    assert_eq!(response.headers.get("X-Custom"), Some(&"kept".to_string()));
+}
+
+#[tokio::test]
+async fn test_logging_middleware_structured_event() {
+    use aws_lambda_router::{handler, Context, Router};
+
+    async fn get_user(_req: Request, _ctx: Context) -> aws_lambda_router::Result<Response> {
+        Ok(Response::ok(json!({})))
+    }
+
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .with_max_level(tracing::Level::TRACE)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let mut router = Router::new();
+    router.use_middleware(LoggingMiddleware::new().include_query(true));
+    router.get("/api/users/:userId", handler!(get_user));
+
+    let event = json!({
+        "requestContext": {"http": {"method": "GET"}, "requestId": "req-42"},
+        "rawPath": "/api/users/7",
+        "headers": {},
+        "queryStringParameters": {"fields": "name"},
+        "body": null
+    });
+    let response = router.dispatch(event).await;
+    assert_eq!(response["statusCode"], 200);
+
+    let output = logs.contents();
+    assert!(output.contains("request completed"), "{}", output);
+    assert!(output.contains("request_id=req-42"), "{}", output);
+    assert!(output.contains("method=GET"), "{}", output);
+    assert!(output.contains("path=/api/users/7?fields=name"), "{}", output);
+    assert!(output.contains(r#"route="/api/users/:userId""#), "{}", output);
+    assert!(output.contains("status=200"), "{}", output);
+    assert!(output.contains("elapsed_ms="), "{}", output);
+}
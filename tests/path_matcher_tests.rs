@@ -116,3 +116,89 @@ fn test_exact_match_required() {
     // Should only match exact path
     assert!(matcher.matches("/api/users").is_some());
 }
+
+#[test]
+fn test_optional_wildcard_empty() {
+    let matcher = PathMatcher::new("/docs/*path");
+
+    let params = matcher.matches("/docs").unwrap();
+    assert_eq!(params.get("path"), Some(&"".to_string()));
+
+    let params = matcher.matches("/docs/").unwrap();
+    assert_eq!(params.get("path"), Some(&"".to_string()));
+
+    assert!(matcher.matches("/docsx").is_none());
+}
+
+#[test]
+fn test_optional_wildcard_captures_rest() {
+    let matcher = PathMatcher::new("/docs/*path");
+
+    let params = matcher.matches("/docs/a/b").unwrap();
+    assert_eq!(params.get("path"), Some(&"a/b".to_string()));
+}
+
+#[test]
+fn test_required_wildcard() {
+    let matcher = PathMatcher::new("/files/:bucket/+key");
+
+    assert!(matcher.matches("/files/photos").is_none());
+    assert!(matcher.matches("/files/photos/").is_none());
+
+    let params = matcher.matches("/files/photos/2024/cat.png").unwrap();
+    assert_eq!(params.get("bucket"), Some(&"photos".to_string()));
+    assert_eq!(params.get("key"), Some(&"2024/cat.png".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "final segment")]
+fn test_wildcard_must_be_last() {
+    PathMatcher::new("/files/*path/meta");
+}
+
+#[test]
+fn test_brace_parameter_syntax() {
+    let colon = PathMatcher::new("/api/users/:userId/posts/:postId");
+    let braces = PathMatcher::new("/api/users/{userId}/posts/{postId}");
+
+    let path = "/api/users/user123/posts/post456";
+    assert_eq!(colon.matches(path), braces.matches(path));
+
+    let params = braces.matches(path).unwrap();
+    assert_eq!(params.get("userId"), Some(&"user123".to_string()));
+    assert_eq!(params.get("postId"), Some(&"post456".to_string()));
+
+    assert!(braces.matches("/api/users/user123/posts").is_none());
+    assert!(braces.matches("/api/users/{userId}/posts/{postId}/extra").is_none());
+}
+
+#[test]
+fn test_mixed_parameter_syntax() {
+    let matcher = PathMatcher::new("/:version/items/{itemId}");
+
+    let params = matcher.matches("/v2/items/abc").unwrap();
+    assert_eq!(params.get("version"), Some(&"v2".to_string()));
+    assert_eq!(params.get("itemId"), Some(&"abc".to_string()));
+}
+
+#[test]
+fn test_param_names() {
+    let matcher = PathMatcher::new("/api/users/:userId/files/*path");
+    assert_eq!(matcher.param_names(), ["userId", "path"]);
+}
+
+#[test]
+fn test_catch_all_deeply_nested_tail() {
+    let matcher = PathMatcher::new("/assets/*path");
+
+    let params = matcher.matches("/assets/").unwrap();
+    assert_eq!(params.get("path"), Some(&"".to_string()));
+
+    let params = matcher.matches("/assets/css/app.css").unwrap();
+    assert_eq!(params.get("path"), Some(&"css/app.css".to_string()));
+
+    let params = matcher.matches("/assets/a/b/c/d/e/f.js").unwrap();
+    assert_eq!(params.get("path"), Some(&"a/b/c/d/e/f.js".to_string()));
+
+    assert!(matcher.matches("/asset/css/app.css").is_none());
+}